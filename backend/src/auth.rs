@@ -0,0 +1,261 @@
+//! This mod contains the extractor and middleware used for authenticating requests and checking the permissions of the logged in account.
+
+use std::{collections::HashSet, convert::Infallible, net::SocketAddr};
+
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts, Request, State},
+    http::{header::USER_AGENT, request::Parts, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::extract::{cookie::Cookie, CookieJar};
+
+use crate::{
+    db_types::{
+        safe_types::{AccountLookup, AuditEventType, AuditOutcome, NewAuditEvent, Permission},
+        unsafe_types::AuthorizedUser,
+    },
+    safe_functions::{
+        check_authenticated_account, is_account_suspended, lookup_account_from_id,
+        lookup_account_permissions, record_audit_event,
+    },
+    PgPool, ServerState,
+};
+
+#[derive(Clone, Debug, Default)]
+/// This struct contains the information about the client which gets recorded in the audit log.
+pub struct ClientInfo {
+    /// The IP address the request has been made from, this is ```None``` if the server wasnt started with connection info
+    pub ip_address: Option<String>,
+    /// The `User-Agent` header of the request
+    pub user_agent: Option<String>,
+}
+
+impl ClientInfo {
+    /// This function reads the client's information out of the parts of a request.
+    pub fn from_parts(parts: &Parts) -> Self {
+        Self {
+            ip_address: parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(address)| address.ip().to_string()),
+            user_agent: parts
+                .headers
+                .get(USER_AGENT)
+                .and_then(|user_agent| user_agent.to_str().ok())
+                .map(String::from),
+        }
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ClientInfo {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(ClientInfo::from_parts(parts))
+    }
+}
+
+#[derive(Clone, Debug)]
+/// This struct is the account a request has been made by, it is extracted from the `session_id` cookie.
+/// Adding it to the arguments of a handler makes the route require a valid session.
+pub struct AuthenticatedAccount {
+    /// The session stored in the database
+    pub session: AuthorizedUser,
+    /// The public information of the account
+    pub account: AccountLookup,
+    /// Every permission the account has through its roles
+    pub permissions: HashSet<Permission>,
+}
+
+impl AuthenticatedAccount {
+    /// This function reads the `session_id` cookie out of the ```CookieJar``` and validates it with the database.
+    /// If the session is valid it will look up the account and its permissions, if it is invalid the rejection is recorded in the audit log.
+    /// Malformed cookies and cookies of nonexistent accounts are only logged, so that anonymous clients can not grow the audit log.
+    pub fn from_cookie_jar(
+        jar: &CookieJar,
+        client: &ClientInfo,
+        pgconnection: PgPool,
+    ) -> Result<Self, AuthenticationRejection> {
+        let session_id_value = jar
+            .get("session_id")
+            .ok_or(AuthenticationRejection::MissingSession)?;
+
+        let reject = |account_id: Option<i32>, details: &str| {
+            // The rejection is returned even if it couldnt be recorded
+            let _ = record_audit_event(
+                NewAuditEvent {
+                    account_id,
+                    details: Some(details.to_string()),
+                    ..NewAuditEvent::from_client(
+                        AuditEventType::SessionRejected,
+                        AuditOutcome::Failure,
+                        client,
+                    )
+                },
+                pgconnection.clone(),
+            );
+
+            AuthenticationRejection::InvalidSession
+        };
+
+        // Anyone can send a malformed cookie, so it is only logged
+        let authorized_user = serde_json::from_str::<AuthorizedUser>(session_id_value.value())
+            .map_err(|_| {
+                tracing::warn!(ip_address = ?client.ip_address, "Malformed session cookie");
+
+                AuthenticationRejection::InvalidSession
+            })?;
+
+        let session = check_authenticated_account(pgconnection.clone(), &authorized_user)
+            .map_err(|_| AuthenticationRejection::DatabaseError)?
+            .ok_or_else(|| {
+                if lookup_account_from_id(authorized_user.account_id, pgconnection.clone()).is_err() {
+                    tracing::warn!(
+                        ip_address = ?client.ip_address,
+                        account_id = authorized_user.account_id,
+                        "Session cookie of a nonexistent account"
+                    );
+
+                    return AuthenticationRejection::InvalidSession;
+                }
+
+                // The account ID of the cookie can still be anyone's, so the event is not recorded under it
+                reject(
+                    None,
+                    &format!(
+                        "Unknown session or mismatching client signature (unverified account ID: {})",
+                        authorized_user.account_id
+                    ),
+                )
+            })?;
+
+        // Accounts hidden automatically by reports can still log in, only suspended ones are rejected
+        if is_account_suspended(session.account_id, pgconnection.clone())
+            .map_err(|_| AuthenticationRejection::DatabaseError)?
+        {
+            return Err(reject(Some(session.account_id), "The account is suspended"));
+        }
+
+        let account = lookup_account_from_id(session.account_id, pgconnection.clone())
+            .map_err(|_| reject(Some(session.account_id), "The account doesnt exist"))?;
+
+        let permissions = lookup_account_permissions(session.account_id, pgconnection)
+            .map_err(|_| AuthenticationRejection::DatabaseError)?
+            .into_iter()
+            .collect();
+
+        Ok(Self {
+            session,
+            account,
+            permissions,
+        })
+    }
+
+    /// This function returns whether the account has the permission specified in the ```permission``` argument.
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<ServerState> for AuthenticatedAccount {
+    type Rejection = AuthenticationRejection;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &ServerState,
+    ) -> Result<Self, Self::Rejection> {
+        // The permission guard has already authenticated the request
+        if let Some(authenticated_account) = parts.extensions.get::<AuthenticatedAccount>() {
+            return Ok(authenticated_account.clone());
+        }
+
+        let jar = CookieJar::from_headers(&parts.headers);
+
+        AuthenticatedAccount::from_cookie_jar(
+            &jar,
+            &ClientInfo::from_parts(parts),
+            state.pgconnection.clone(),
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// This enum describes why a request could not be authenticated.
+pub enum AuthenticationRejection {
+    /// The request has no `session_id` cookie
+    MissingSession,
+    /// The `session_id` cookie is malformed, expired or belongs to an account which can not log in
+    InvalidSession,
+    /// The session could not be validated because of a database error
+    DatabaseError,
+    /// The account doesnt have the permission required by the route
+    MissingPermission,
+}
+
+impl IntoResponse for AuthenticationRejection {
+    fn into_response(self) -> Response {
+        match self {
+            AuthenticationRejection::MissingSession => StatusCode::UNAUTHORIZED.into_response(),
+            // Remove the invalid cookie so that the client doesnt keep sending it
+            AuthenticationRejection::InvalidSession => (
+                CookieJar::new().remove(Cookie::build("session_id").path("/")),
+                StatusCode::UNAUTHORIZED,
+            )
+                .into_response(),
+            AuthenticationRejection::DatabaseError => {
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+            AuthenticationRejection::MissingPermission => StatusCode::FORBIDDEN.into_response(),
+        }
+    }
+}
+
+/// This function is a middleware which only lets the request through if the logged in account has the ```Permission``` passed in with the state.
+/// If the account doesnt have the permission it will return ```StatusCode::FORBIDDEN```, the ```AuthenticatedAccount``` is passed on to the handler so it doesnt have to be looked up again.
+/// Every request which can change something (everything but `GET`) is recorded in the audit log together with its outcome.
+pub async fn permission_guard(
+    State((state, permission)): State<(ServerState, Permission)>,
+    jar: CookieJar,
+    client: ClientInfo,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AuthenticationRejection> {
+    let authenticated_account =
+        AuthenticatedAccount::from_cookie_jar(&jar, &client, state.pgconnection.clone())?;
+
+    let account_id = authenticated_account.account.id;
+
+    if !authenticated_account.has_permission(permission) {
+        return Err(AuthenticationRejection::MissingPermission);
+    }
+
+    let details = format!("{} {}", request.method(), request.uri().path());
+    let is_read_only = request.method() == Method::GET;
+
+    request.extensions_mut().insert(authenticated_account);
+
+    let response = next.run(request).await;
+
+    if !is_read_only {
+        let outcome = if response.status().is_success() {
+            AuditOutcome::Success
+        } else {
+            AuditOutcome::Failure
+        };
+
+        let _ = record_audit_event(
+            NewAuditEvent {
+                account_id: Some(account_id),
+                details: Some(details),
+                ..NewAuditEvent::from_client(AuditEventType::AdminAction, outcome, &client)
+            },
+            state.pgconnection.clone(),
+        );
+    }
+
+    Ok(response)
+}
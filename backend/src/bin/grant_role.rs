@@ -0,0 +1,22 @@
+//! This binary grants a role to an account, it is used for creating the first admin account.
+//! Usage: `grant_role <username> [role]`, the role defaults to `admin`.
+
+use backend::{establish_server_state, safe_functions::grant_role};
+
+fn main() -> anyhow::Result<()> {
+    let mut args = std::env::args().skip(1);
+
+    let Some(username) = args.next() else {
+        anyhow::bail!("Usage: grant_role <username> [role]");
+    };
+
+    let role = args.next().unwrap_or_else(|| String::from("admin"));
+
+    let state = establish_server_state()?;
+
+    grant_role(&username, &role, state.pgconnection)?;
+
+    println!("Granted the `{role}` role to `{username}`.");
+
+    Ok(())
+}
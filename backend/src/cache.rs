@@ -0,0 +1,298 @@
+//! This mod contains the in-process cache of the public read endpoints, and the `ETag` handling of their responses.
+//! Entries are invalidated as soon as the rows they were built from change, the TTLs only bound how stale they can get when the rows are changed by another process (e.g. when an automatic hide expires or another instance takes a moderation action).
+
+use std::time::Duration;
+
+use axum::{
+    body::Bytes,
+    extract::Request,
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_NONE_MATCH},
+        HeaderMap, HeaderValue, Method, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use moka::sync::Cache;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    db_types::safe_types::TargetKind,
+    safe_functions::{
+        list_categories, list_category_attributes, list_exchange_rates,
+        lookup_visible_account_from_id,
+    },
+    PgPool,
+};
+
+/// The time an account lookup is cached for
+const ACCOUNT_TTL: Duration = Duration::from_secs(60);
+
+/// The time the category tree, the category attributes and the exchange rates are cached for, these are only changed by admins.
+const CATALOG_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// The maximum number of account lookups kept in the cache
+const MAX_CACHED_ACCOUNTS: u64 = 10_000;
+
+/// The maximum number of attribute schemas kept in the cache
+const MAX_CACHED_CATEGORY_ATTRIBUTES: u64 = 1_000;
+
+/// The `Cache-Control` of the cached responses, clients can keep them but have to revalidate them with their `ETag` before every use.
+const CACHED_RESPONSE_CACHE_CONTROL: &str = "no-cache";
+
+/// A JSON response body serialized once, together with its `ETag`, so that cache hits dont have to be serialized again.
+#[derive(Clone, Debug)]
+pub struct CachedJson {
+    body: Bytes,
+    etag: HeaderValue,
+}
+
+impl CachedJson {
+    /// This function serializes the value and computes the `ETag` of it from the hash of the body.
+    pub fn new<T: Serialize>(value: &T) -> anyhow::Result<Self> {
+        let body = Bytes::from(serde_json::to_vec(value)?);
+
+        let etag = HeaderValue::from_str(&format!(
+            "\"{}\"",
+            URL_SAFE_NO_PAD.encode(Sha256::digest(&body))
+        ))?;
+
+        Ok(Self { body, etag })
+    }
+}
+
+impl IntoResponse for CachedJson {
+    fn into_response(self) -> Response {
+        (
+            [
+                (CONTENT_TYPE, HeaderValue::from_static("application/json")),
+                (
+                    CACHE_CONTROL,
+                    HeaderValue::from_static(CACHED_RESPONSE_CACHE_CONTROL),
+                ),
+                (ETAG, self.etag),
+            ],
+            self.body,
+        )
+            .into_response()
+    }
+}
+
+/// The cached responses of the public read endpoints, the ```ServerState``` holds one of these shared by every request.
+#[derive(Clone)]
+pub struct ResponseCache {
+    /// The visible accounts by their ID
+    accounts: Cache<i32, CachedJson>,
+    /// Every category
+    categories: Cache<(), CachedJson>,
+    /// The attribute schemas by the ID of their category
+    category_attributes: Cache<i32, CachedJson>,
+    /// Every exchange rate
+    exchange_rates: Cache<(), CachedJson>,
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResponseCache {
+    pub fn new() -> Self {
+        Self {
+            accounts: Cache::builder()
+                .max_capacity(MAX_CACHED_ACCOUNTS)
+                .time_to_live(ACCOUNT_TTL)
+                .build(),
+            categories: Cache::builder().time_to_live(CATALOG_TTL).build(),
+            category_attributes: Cache::builder()
+                .max_capacity(MAX_CACHED_CATEGORY_ATTRIBUTES)
+                .time_to_live(CATALOG_TTL)
+                .build(),
+            exchange_rates: Cache::builder().time_to_live(CATALOG_TTL).build(),
+        }
+    }
+
+    /// This function returns the public information of the account the same way ```lookup_visible_account_from_id``` does, only querying the database on a cache miss.
+    /// Accounts which are not found or hidden are not cached, so that they show up as soon as they are visible.
+    pub fn account(&self, id: i32, pgconnection: PgPool) -> anyhow::Result<CachedJson> {
+        get_or_load(&self.accounts, id, || {
+            lookup_visible_account_from_id(id, pgconnection)
+        })
+    }
+
+    /// This function returns every category the same way ```list_categories``` does, only querying the database on a cache miss.
+    pub fn categories(&self, pgconnection: PgPool) -> anyhow::Result<CachedJson> {
+        get_or_load(&self.categories, (), || list_categories(pgconnection))
+    }
+
+    /// This function returns the attribute schema of the category the same way ```list_category_attributes``` does, only querying the database on a cache miss.
+    pub fn category_attributes(
+        &self,
+        category_id: i32,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<CachedJson> {
+        get_or_load(&self.category_attributes, category_id, || {
+            list_category_attributes(category_id, pgconnection)
+        })
+    }
+
+    /// This function returns every exchange rate the same way ```list_exchange_rates``` does, only querying the database on a cache miss.
+    pub fn exchange_rates(&self, pgconnection: PgPool) -> anyhow::Result<CachedJson> {
+        get_or_load(&self.exchange_rates, (), || {
+            list_exchange_rates(pgconnection)
+        })
+    }
+
+    /// This function invalidates the cached responses of the target, it should be called whenever a report or a moderation action could have changed its visibility.
+    pub fn invalidate_target(&self, target_kind: TargetKind, target_id: i32) {
+        match target_kind {
+            TargetKind::Account => self.accounts.invalidate(&target_id),
+        }
+    }
+
+    /// This function invalidates the cached category tree and every cached attribute schema, as deleting a category deletes its subcategories and their attributes too.
+    pub fn invalidate_categories(&self) {
+        self.categories.invalidate_all();
+        self.category_attributes.invalidate_all();
+    }
+
+    /// This function invalidates every cached attribute schema, as the attributes are deleted by their own ID, without knowing their category.
+    pub fn invalidate_category_attributes(&self) {
+        self.category_attributes.invalidate_all();
+    }
+
+    /// This function invalidates the cached exchange rates.
+    pub fn invalidate_exchange_rates(&self) {
+        self.exchange_rates.invalidate(&());
+    }
+}
+
+/// This function returns the cached response of the key, or loads, serializes and caches it on a miss.
+/// Concurrent misses of the same key only load it once.
+fn get_or_load<K, T>(
+    cache: &Cache<K, CachedJson>,
+    key: K,
+    load: impl FnOnce() -> anyhow::Result<T>,
+) -> anyhow::Result<CachedJson>
+where
+    K: std::hash::Hash + Eq + Send + Sync + 'static,
+    T: Serialize,
+{
+    cache
+        .try_get_with(key, || CachedJson::new(&load()?))
+        .map_err(|err| anyhow::Error::msg(err.to_string()))
+}
+
+/// This function returns whether the `If-None-Match` header contains the tag, using the weak comparison, as the tags are only used for caching.
+pub fn etag_matches(if_none_match: &HeaderValue, etag: &HeaderValue) -> bool {
+    let Ok(if_none_match) = if_none_match.to_str() else {
+        return false;
+    };
+    let Ok(etag) = etag.to_str() else {
+        return false;
+    };
+
+    let etag = etag.trim_start_matches("W/");
+
+    if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// This middleware replaces the response with ```StatusCode::NOT_MODIFIED``` if it has an `ETag` which the `If-None-Match` header of the request already contains.
+/// Only successful `GET` and `HEAD` requests are answered this way, the handlers set the `ETag` themselves (e.g. by returning a ```CachedJson```).
+pub async fn not_modified(request: Request, next: Next) -> Response {
+    let if_none_match = match *request.method() {
+        Method::GET | Method::HEAD => request.headers().get(IF_NONE_MATCH).cloned(),
+        _ => None,
+    };
+
+    let response = next.run(request).await;
+
+    let Some(if_none_match) = if_none_match else {
+        return response;
+    };
+
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let Some(etag) = response.headers().get(ETAG) else {
+        return response;
+    };
+
+    if !etag_matches(&if_none_match, etag) {
+        return response;
+    }
+
+    let mut headers = HeaderMap::new();
+
+    headers.insert(ETAG, etag.clone());
+
+    if let Some(cache_control) = response.headers().get(CACHE_CONTROL) {
+        headers.insert(CACHE_CONTROL, cache_control.clone());
+    }
+
+    (StatusCode::NOT_MODIFIED, headers).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn if_none_match_is_compared_weakly() {
+        let etag = HeaderValue::from_static("\"abc\"");
+
+        assert!(etag_matches(&HeaderValue::from_static("\"abc\""), &etag));
+        assert!(etag_matches(&HeaderValue::from_static("W/\"abc\""), &etag));
+        assert!(etag_matches(
+            &HeaderValue::from_static("\"abc\""),
+            &HeaderValue::from_static("W/\"abc\"")
+        ));
+        assert!(etag_matches(
+            &HeaderValue::from_static("\"xyz\", W/\"abc\""),
+            &etag
+        ));
+        assert!(etag_matches(&HeaderValue::from_static("*"), &etag));
+        assert!(etag_matches(
+            &HeaderValue::from_static("\"xyz\" , *"),
+            &etag
+        ));
+
+        assert!(!etag_matches(&HeaderValue::from_static("\"xyz\""), &etag));
+        assert!(!etag_matches(
+            &HeaderValue::from_static("\"xyz\", W/\"abcd\""),
+            &etag
+        ));
+        assert!(!etag_matches(&HeaderValue::from_static(""), &etag));
+    }
+
+    #[test]
+    fn invalidate_target_evicts_the_cached_account() {
+        let cache = ResponseCache::new();
+        let response = CachedJson::new(&"account").unwrap();
+
+        cache.accounts.insert(1, response.clone());
+        cache.accounts.insert(2, response);
+
+        cache.invalidate_target(TargetKind::Account, 1);
+
+        assert!(cache.accounts.get(&1).is_none());
+        assert!(cache.accounts.get(&2).is_some());
+    }
+
+    #[test]
+    fn same_body_gets_the_same_etag() {
+        let first = CachedJson::new(&vec![1, 2, 3]).unwrap();
+        let second = CachedJson::new(&vec![1, 2, 3]).unwrap();
+        let different = CachedJson::new(&vec![1, 2]).unwrap();
+
+        assert_eq!(first.etag, second.etag);
+        assert_ne!(first.etag, different.etag);
+    }
+}
@@ -0,0 +1,145 @@
+//! This mod contains the CSRF protection of the state-changing routes, which uses the double-submit cookie pattern.
+//! Every client gets a random token in the `csrf_token` cookie, which it has to echo back in the `X-CSRF-Token` header of every state-changing request.
+//! A cross-site page can make the browser send the cookie, but it can not read it, so it can not set the header.
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use axum::{
+    extract::{Request, State},
+    http::{
+        header::{AUTHORIZATION, COOKIE, HOST, ORIGIN, REFERER},
+        HeaderMap, HeaderName, HeaderValue, StatusCode, Uri,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::extract::{
+    cookie::{Cookie, SameSite},
+    CookieJar,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use subtle::ConstantTimeEq;
+
+/// The name of the cookie the CSRF token is stored in, it is readable by the frontend so that it can copy it into ```CSRF_HEADER```.
+pub const CSRF_COOKIE_NAME: &str = "csrf_token";
+
+/// The header the CSRF token has to be sent back in.
+pub const CSRF_HEADER: HeaderName = HeaderName::from_static("x-csrf-token");
+
+/// The number of random bytes a CSRF token consists of
+const CSRF_TOKEN_BYTES: usize = 32;
+
+/// This function generates a new random CSRF token, encoded as URL-safe base64.
+fn generate_csrf_token() -> String {
+    let mut token = [0_u8; CSRF_TOKEN_BYTES];
+
+    OsRng.fill_bytes(&mut token);
+
+    URL_SAFE_NO_PAD.encode(token)
+}
+
+/// This function compares the two tokens in constant time, so that the token can not be guessed byte by byte from the response times.
+fn tokens_match(cookie_token: &str, header_token: &str) -> bool {
+    cookie_token
+        .as_bytes()
+        .ct_eq(header_token.as_bytes())
+        .into()
+}
+
+/// This function returns whether the request is made by an API client authenticating itself with the `Authorization` header instead of cookies.
+/// Browsers never attach this header to cross-site requests on their own, so these requests can not be forged.
+fn is_token_authenticated(headers: &HeaderMap) -> bool {
+    headers.contains_key(AUTHORIZATION) && !headers.contains_key(COOKIE)
+}
+
+/// This function returns the origin (`scheme://host[:port]`) of the URL, or ```None``` if it is not an absolute URL.
+fn origin_of_url(url: &str) -> Option<String> {
+    let uri = url.parse::<Uri>().ok()?;
+
+    Some(format!("{}://{}", uri.scheme_str()?, uri.authority()?))
+}
+
+/// This function checks the `Origin` header of the request, or the `Referer` header if the browser didnt send an origin.
+/// The origin is trusted if it is in the allowlist or it is the server's own, requests without either header are left to the token check.
+fn has_trusted_origin(headers: &HeaderMap, allowed_origins: &[HeaderValue]) -> bool {
+    let origin = match headers.get(ORIGIN) {
+        Some(origin) => origin.to_str().ok().map(str::to_string),
+        None => match headers.get(REFERER) {
+            Some(referer) => referer.to_str().ok().and_then(origin_of_url),
+            None => return true,
+        },
+    };
+
+    let Some(origin) = origin else {
+        return false;
+    };
+
+    if allowed_origins
+        .iter()
+        .any(|allowed_origin| allowed_origin == origin.as_str())
+    {
+        return true;
+    }
+
+    // Same-origin requests are always allowed, the scheme is not compared as TLS can be terminated in front of the server
+    let host = headers.get(HOST).and_then(|host| host.to_str().ok());
+
+    match (origin.parse::<Uri>().ok(), host) {
+        (Some(origin), Some(host)) => origin
+            .authority()
+            .is_some_and(|authority| authority == host),
+        _ => false,
+    }
+}
+
+/// This middleware rejects every state-changing request which fails the CSRF checks with ```StatusCode::FORBIDDEN```.
+/// A request passes if its origin is trusted and the token in ```CSRF_HEADER``` matches the one in the ```CSRF_COOKIE_NAME``` cookie, requests authenticated via the `Authorization` header are exempt.
+/// It also issues the cookie to every client which doesnt have one yet, so the token is available before the first state-changing request.
+pub async fn csrf_protection(
+    State(allowed_origins): State<Vec<HeaderValue>>,
+    jar: CookieJar,
+    request: Request,
+    next: Next,
+) -> Response {
+    let cookie_token = jar
+        .get(CSRF_COOKIE_NAME)
+        .map(|cookie| cookie.value().to_string());
+
+    let is_safe_method = request.method().is_safe();
+
+    if !is_safe_method && !is_token_authenticated(request.headers()) {
+        if !has_trusted_origin(request.headers(), &allowed_origins) {
+            return (StatusCode::FORBIDDEN, "Cross-origin request rejected").into_response();
+        }
+
+        let header_token = request
+            .headers()
+            .get(CSRF_HEADER)
+            .and_then(|token| token.to_str().ok());
+
+        match (&cookie_token, header_token) {
+            (Some(cookie_token), Some(header_token))
+                if tokens_match(cookie_token, header_token) => {}
+            _ => return (StatusCode::FORBIDDEN, "Missing or invalid CSRF token").into_response(),
+        }
+    }
+
+    let response = next.run(request).await;
+
+    if cookie_token.is_some() {
+        return response;
+    }
+
+    (
+        jar.add(
+            Cookie::build(Cookie::new(CSRF_COOKIE_NAME, generate_csrf_token()))
+                .permanent()
+                .path("/")
+                .secure(true)
+                .http_only(false)
+                .same_site(SameSite::Strict)
+                .build(),
+        ),
+        response,
+    )
+        .into_response()
+}
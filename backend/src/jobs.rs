@@ -0,0 +1,727 @@
+//! This mod contains the durable background job queue, which is backed by the `jobs` table.
+//! Jobs are claimed with `SELECT ... FOR UPDATE SKIP LOCKED`, so any number of workers and server instances can share the queue without running a job twice.
+//! A failed job is retried with exponential backoff, once it has used up its attempts it is moved to the dead-letter state, where it stays until it is retried by hand with ```retry_dead_job```.
+//! Recurring jobs are listed in ```SCHEDULED_JOBS```, the number of workers is read from the `JOB_WORKER_COUNT` environment variable.
+
+use std::{io::Write, str::FromStr, time::Duration};
+
+use chrono::{NaiveDateTime, Utc};
+use diesel::{
+    deserialize::{self, FromSql, FromSqlRow},
+    dsl::insert_into,
+    expression::AsExpression,
+    pg::{expression::extensions::IntervalDsl, Pg, PgValue},
+    serialize::{self, IsNull, Output, ToSql},
+    sql_types::Text,
+    BoolExpressionMethods, ExpressionMethods, NullableExpressionMethods, OptionalExtension,
+    PgConnection, QueryDsl, Queryable, RunQueryDsl, Selectable, SelectableHelper,
+};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinSet;
+use tracing::instrument;
+
+use crate::{
+    db_types::safe_types::NewNotification,
+    safe_functions::prune_audit_events,
+    schema::{job_schedules, jobs},
+    send_notification, PgPool, ServerState, AUDIT_EVENT_RETENTION_DAYS,
+};
+
+/// The number of workers started if the `JOB_WORKER_COUNT` environment variable is not set
+const DEFAULT_WORKER_COUNT: usize = 4;
+
+/// The time an idle worker waits for before checking the queue again
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The time between two checks for due scheduled jobs, this is the precision the scheduled jobs are enqueued with.
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(15);
+
+/// The number of seconds a job can be running for before it is considered abandoned (e.g. because its server crashed) and gets claimed again.
+/// Jobs should finish well within this time, otherwise they can end up running twice.
+const LOCK_TIMEOUT_SECONDS: i32 = 10 * 60;
+
+/// The number of seconds the first retry of a failed job is delayed by, every further retry waits twice as long as the previous one.
+const BASE_RETRY_DELAY_SECONDS: i64 = 10;
+
+/// The longest a retry can be delayed by
+const MAX_RETRY_DELAY_SECONDS: i64 = 60 * 60;
+
+/// The recurring jobs, the first run of a new schedule is the first time matching its cron expression after it gets deployed.
+pub const SCHEDULED_JOBS: [ScheduledJob; 1] = [ScheduledJob {
+    name: "prune_audit_events",
+    cron: "0 0 3 * * *",
+    job: || Job::PruneAuditEvents,
+}];
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "kind", content = "payload", rename_all = "snake_case")]
+/// This enum contains every kind of job, together with its payload.
+/// It is stored as JSON in the `payload` column, so renaming a variant or a field makes the already enqueued jobs of that kind end up in the dead-letter state.
+/// The saved search alerts (see ```AlertFrequency```) will be sent by a job scheduled per frequency once there are listings to match the searches against.
+pub enum Job {
+    /// Deletes the events older than ```AUDIT_EVENT_RETENTION_DAYS``` from the audit log
+    PruneAuditEvents,
+    /// Sends the notification via ```send_notification```, this can be used to notify an account later (e.g. with ```schedule_job```)
+    SendNotification(NewNotification),
+}
+
+impl Job {
+    /// This function returns the name of the kind of this job, which is stored next to the payload and used as the label of the job metrics.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Job::PruneAuditEvents => "prune_audit_events",
+            Job::SendNotification(_) => "send_notification",
+        }
+    }
+
+    /// This function returns the number of times this job is attempted before it is moved to the dead-letter state.
+    pub fn max_attempts(&self) -> i32 {
+        match self {
+            // The audit log is pruned again on the next day anyway
+            Job::PruneAuditEvents => 3,
+            Job::SendNotification(_) => 5,
+        }
+    }
+
+    /// This function runs the job, it blocks while the job is running, so it should not be called from an async context directly.
+    fn run(self, state: &ServerState) -> anyhow::Result<()> {
+        match self {
+            Job::PruneAuditEvents => {
+                prune_audit_events(AUDIT_EVENT_RETENTION_DAYS, state.pgconnection.clone())?;
+            }
+            Job::SendNotification(notification) => {
+                send_notification(state, notification)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(AsExpression, FromSqlRow, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[diesel(sql_type = diesel::sql_types::Text)]
+#[serde(rename_all = "snake_case")]
+/// This enum describes where a job is in the queue, the jobs which succeeded are deleted.
+/// It is stored as text in the database.
+pub enum JobStatus {
+    /// The job is waiting for its `run_at` time or for a free worker
+    Pending,
+    /// A worker has claimed the job and is running it
+    Running,
+    /// The job has used up its attempts, or its payload is not recognized, it wont be run again unless it is retried by hand
+    Dead,
+}
+
+impl JobStatus {
+    /// This function returns the text representation of this ```JobStatus```, which is used when storing it in the database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Dead => "dead",
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for JobStatus {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        out.write_all(self.as_str().as_bytes())?;
+
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Pg> for JobStatus {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"pending" => Ok(JobStatus::Pending),
+            b"running" => Ok(JobStatus::Running),
+            b"dead" => Ok(JobStatus::Dead),
+            _ => Err("Unrecognized job status".into()),
+        }
+    }
+}
+
+#[derive(Queryable, Selectable, Clone, Debug)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(table_name = jobs)]
+/// This struct is a job stored in the queue.
+pub struct QueuedJob {
+    pub id: i32,
+    /// The value of ```Job::kind```
+    pub kind: String,
+    /// The ```Job``` serialized as JSON
+    pub payload: serde_json::Value,
+    pub status: JobStatus,
+    /// The number of times the job has been claimed, including the current run
+    pub attempts: i32,
+    pub max_attempts: i32,
+    /// The job is not claimed before this time, this is pushed back after every failed attempt
+    pub run_at: NaiveDateTime,
+    /// The time the job has been claimed at, if it is running
+    pub locked_at: Option<NaiveDateTime>,
+    /// The error of the last failed attempt
+    pub last_error: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl QueuedJob {
+    /// This function returns whether the job has been claimed more times than its ```max_attempts```.
+    /// The attempts are counted when the job is claimed, so such a job has been abandoned by its worker every time, and it is not run again.
+    fn is_abandoned(&self) -> bool {
+        self.attempts > self.max_attempts
+    }
+
+    /// This function returns the status the job is moved to after its current attempt failed.
+    /// It is retried unless it has used up its attempts or ```retryable``` is false, in which case it is moved to the dead-letter state.
+    fn status_after_failure(&self, retryable: bool) -> JobStatus {
+        if !retryable || self.attempts >= self.max_attempts {
+            JobStatus::Dead
+        } else {
+            JobStatus::Pending
+        }
+    }
+}
+
+/// A job which is enqueued repeatedly, at the times matching its cron expression.
+pub struct ScheduledJob {
+    /// The unique name of the schedule, this is the key of its row in the `job_schedules` table
+    pub name: &'static str,
+    /// The cron expression of the times the job is enqueued at in UTC, with a leading seconds field (e.g. `0 0 3 * * *` is every day at 03:00)
+    pub cron: &'static str,
+    /// This function creates the job enqueued at every run
+    pub job: fn() -> Job,
+}
+
+impl ScheduledJob {
+    /// This function returns the first time matching the cron expression after the time passed in, in UTC.
+    fn next_run_after(&self, after: NaiveDateTime) -> anyhow::Result<NaiveDateTime> {
+        let schedule = cron::Schedule::from_str(self.cron)?;
+
+        let Some(next_run) = schedule.after(&after.and_utc()).next() else {
+            anyhow::bail!("The cron expression of {} never matches", self.name)
+        };
+
+        Ok(next_run.naive_utc())
+    }
+}
+
+/// This function reads the number of workers from the `JOB_WORKER_COUNT` environment variable.
+/// If the variable is not set ```DEFAULT_WORKER_COUNT``` is used.
+pub fn worker_count_from_env() -> anyhow::Result<usize> {
+    match std::env::var("JOB_WORKER_COUNT") {
+        Ok(worker_count) => Ok(worker_count.parse()?),
+        Err(std::env::VarError::NotPresent) => Ok(DEFAULT_WORKER_COUNT),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// This function adds the job to the queue, it is run as soon as a worker is free.
+/// The ID of the stored job is returned.
+pub fn enqueue_job(job: &Job, pgconnection: PgPool) -> anyhow::Result<i32> {
+    schedule_job(job, Duration::ZERO, pgconnection)
+}
+
+/// This function adds the job to the queue, it is run once the delay has passed.
+/// The ID of the stored job is returned.
+pub fn schedule_job(job: &Job, delay: Duration, pgconnection: PgPool) -> anyhow::Result<i32> {
+    let mut conn = pgconnection.get()?;
+
+    insert_job(&mut conn, job, delay)
+}
+
+/// This function adds the job to the queue on the connection passed in, so that it can be enqueued in the same transaction as the changes it belongs to.
+/// The ID of the stored job is returned.
+#[instrument(skip(conn), fields(kind = job.kind()), err(level = "warn"))]
+pub fn insert_job(conn: &mut PgConnection, job: &Job, delay: Duration) -> anyhow::Result<i32> {
+    let delay_seconds = i64::try_from(delay.as_secs())?;
+
+    insert_into(jobs::table)
+        .values((
+            jobs::kind.eq(job.kind()),
+            jobs::payload.eq(serde_json::to_value(job)?),
+            jobs::max_attempts.eq(job.max_attempts()),
+            jobs::run_at.eq(diesel::dsl::now + delay_seconds.seconds()),
+        ))
+        .returning(jobs::id)
+        .get_result(conn)
+        .map_err(anyhow::Error::from)
+}
+
+/// This function claims the job which has been due for the longest, or an abandoned one, and marks it as running.
+/// The row is locked with `SKIP LOCKED` while it is claimed, so the jobs being claimed by other workers are skipped instead of waited for.
+/// It returns ```None``` if there is no job to run.
+#[instrument(skip(pgconnection), err(level = "warn"))]
+fn claim_job(pgconnection: PgPool) -> anyhow::Result<Option<QueuedJob>> {
+    pgconnection
+        .get()?
+        .build_transaction()
+        .read_write()
+        .run(|conn| {
+            let claimable_job =
+                jobs::table
+                    .filter(
+                        jobs::status
+                            .eq(JobStatus::Pending)
+                            .and(jobs::run_at.le(diesel::dsl::now))
+                            .or(jobs::status.eq(JobStatus::Running).and(jobs::locked_at.le(
+                                (diesel::dsl::now - LOCK_TIMEOUT_SECONDS.seconds()).nullable(),
+                            ))),
+                    )
+                    .order(jobs::run_at.asc())
+                    .select(jobs::id)
+                    .for_update()
+                    .skip_locked()
+                    .first::<i32>(conn)
+                    .optional()?;
+
+            let Some(job_id) = claimable_job else {
+                return Ok(None);
+            };
+
+            diesel::update(jobs::table.find(job_id))
+                .set((
+                    jobs::status.eq(JobStatus::Running),
+                    jobs::locked_at.eq(diesel::dsl::now.nullable()),
+                    jobs::attempts.eq(jobs::attempts + 1),
+                ))
+                .returning(QueuedJob::as_returning())
+                .get_result(conn)
+                .map(Some)
+                .map_err(anyhow::Error::from)
+        })
+}
+
+/// This function removes the job from the queue after it has succeeded, and returns the number of jobs removed.
+/// The job is only removed if it is still claimed by this worker, so it returns 0 if the job has been claimed by another one after its lock timed out.
+#[instrument(skip(job, pgconnection), fields(job_id = job.id), err(level = "warn"))]
+fn complete_job(job: &QueuedJob, pgconnection: PgPool) -> anyhow::Result<usize> {
+    diesel::delete(
+        jobs::table
+            .find(job.id)
+            .filter(jobs::status.eq(JobStatus::Running))
+            .filter(jobs::locked_at.eq(job.locked_at)),
+    )
+    .execute(&mut pgconnection.get()?)
+    .map_err(anyhow::Error::from)
+}
+
+/// This function records the failed attempt of the job, and returns the status it has been moved to.
+/// The job is retried after ```retry_delay_seconds```, or moved to the dead-letter state, depending on ```QueuedJob::status_after_failure```.
+/// The job is only updated if it is still claimed by this worker, so it returns ```None``` if the job has been claimed by another one after its lock timed out.
+#[instrument(skip(job, pgconnection), fields(job_id = job.id), err(level = "warn"))]
+fn fail_job(
+    job: &QueuedJob,
+    error: &str,
+    retryable: bool,
+    pgconnection: PgPool,
+) -> anyhow::Result<Option<JobStatus>> {
+    let mut conn = pgconnection.get()?;
+
+    let claimed_job = jobs::table
+        .find(job.id)
+        .filter(jobs::status.eq(JobStatus::Running))
+        .filter(jobs::locked_at.eq(job.locked_at));
+
+    let status = job.status_after_failure(retryable);
+
+    let updated_rows = match status {
+        JobStatus::Dead => diesel::update(claimed_job)
+            .set((
+                jobs::status.eq(JobStatus::Dead),
+                jobs::locked_at.eq(None::<NaiveDateTime>),
+                jobs::last_error.eq(error),
+            ))
+            .execute(&mut conn)?,
+        JobStatus::Pending | JobStatus::Running => diesel::update(claimed_job)
+            .set((
+                jobs::status.eq(JobStatus::Pending),
+                jobs::locked_at.eq(None::<NaiveDateTime>),
+                jobs::run_at.eq(diesel::dsl::now + retry_delay_seconds(job.attempts).seconds()),
+                jobs::last_error.eq(error),
+            ))
+            .execute(&mut conn)?,
+    };
+
+    Ok((updated_rows != 0).then_some(status))
+}
+
+/// This function returns the number of seconds the next attempt of a job is delayed by after its ```attempts```th attempt failed.
+fn retry_delay_seconds(attempts: i32) -> i64 {
+    let exponent = u32::try_from(attempts.saturating_sub(1)).unwrap_or_default();
+
+    2_i64
+        .saturating_pow(exponent)
+        .saturating_mul(BASE_RETRY_DELAY_SECONDS)
+        .min(MAX_RETRY_DELAY_SECONDS)
+}
+
+/// This function moves the job specified in the ```job_id``` argument out of the dead-letter state, so that it is run again with all of its attempts.
+/// It returns the number of jobs retried, which is 0 if the job doesnt exist or is not dead.
+#[instrument(skip(pgconnection), err(level = "warn"))]
+pub fn retry_dead_job(job_id: i32, pgconnection: PgPool) -> anyhow::Result<usize> {
+    diesel::update(
+        jobs::table
+            .find(job_id)
+            .filter(jobs::status.eq(JobStatus::Dead)),
+    )
+    .set((
+        jobs::status.eq(JobStatus::Pending),
+        jobs::attempts.eq(0),
+        jobs::run_at.eq(diesel::dsl::now),
+    ))
+    .execute(&mut pgconnection.get()?)
+    .map_err(anyhow::Error::from)
+}
+
+/// This function enqueues every scheduled job which is due, and moves its schedule to its next run.
+/// The due schedules are locked with `SKIP LOCKED`, so when several server instances check them at once, every job is only enqueued by one of them.
+/// The schedules are compared against the clock of the server instead of the database's, as the cron expressions are evaluated in UTC.
+/// It returns the number of jobs enqueued.
+#[instrument(skip(pgconnection), err(level = "warn"))]
+fn enqueue_due_scheduled_jobs(pgconnection: PgPool) -> anyhow::Result<usize> {
+    pgconnection
+        .get()?
+        .build_transaction()
+        .read_write()
+        .run(|conn| {
+            let now = Utc::now().naive_utc();
+
+            // The schedules added since the last check start at their next run
+            for scheduled_job in &SCHEDULED_JOBS {
+                insert_into(job_schedules::table)
+                    .values((
+                        job_schedules::name.eq(scheduled_job.name),
+                        job_schedules::next_run_at.eq(scheduled_job.next_run_after(now)?),
+                    ))
+                    .on_conflict_do_nothing()
+                    .execute(conn)?;
+            }
+
+            let due_schedules: Vec<(String, NaiveDateTime)> = job_schedules::table
+                .filter(job_schedules::next_run_at.le(now))
+                .select((job_schedules::name, job_schedules::next_run_at))
+                .for_update()
+                .skip_locked()
+                .load(conn)?;
+
+            let mut enqueued_jobs = 0;
+
+            for (schedule_name, due_run) in due_schedules {
+                // The rows of the schedules which have been removed are left alone, so that they can be added back
+                let Some(scheduled_job) = SCHEDULED_JOBS
+                    .iter()
+                    .find(|scheduled_job| scheduled_job.name == schedule_name)
+                else {
+                    continue;
+                };
+
+                insert_job(conn, &(scheduled_job.job)(), Duration::ZERO)?;
+
+                // The runs missed while no server was running are skipped, a single job is enqueued for all of them
+                diesel::update(job_schedules::table.find(&schedule_name))
+                    .set(
+                        job_schedules::next_run_at
+                            .eq(scheduled_job.next_run_after(now.max(due_run))?),
+                    )
+                    .execute(conn)?;
+
+                enqueued_jobs += 1;
+            }
+
+            Ok(enqueued_jobs)
+        })
+}
+
+/// This function starts the workers and the scheduler of the job queue, they stop once the ```shutdown``` token of the state gets cancelled.
+/// The workers finish the jobs they are running before stopping, the returned ```JoinSet``` can be awaited for that.
+pub fn start_job_workers(state: ServerState, worker_count: usize) -> JoinSet<()> {
+    let mut workers = JoinSet::new();
+
+    for worker_id in 0..worker_count {
+        workers.spawn(run_worker(state.clone(), worker_id));
+    }
+
+    workers.spawn(run_scheduler(state));
+
+    workers
+}
+
+/// This function claims and runs jobs one by one until the server starts shutting down.
+#[instrument(skip(state))]
+async fn run_worker(state: ServerState, worker_id: usize) {
+    while !state.shutdown.is_cancelled() {
+        let pgconnection = state.pgconnection.clone();
+
+        match tokio::task::spawn_blocking(move || claim_job(pgconnection)).await {
+            Ok(Ok(Some(job))) => run_job(&state, job).await,
+            // The queue is empty or the database is unreachable, either way it is checked again later
+            _ => {
+                tokio::select! {
+                    _ = tokio::time::sleep(POLL_INTERVAL) => {},
+                    _ = state.shutdown.cancelled() => {},
+                }
+            }
+        }
+    }
+}
+
+/// This function runs the claimed job, then removes it from the queue or records its failure.
+#[instrument(skip_all, fields(job_id = job.id, kind = %job.kind, attempt = job.attempts))]
+async fn run_job(state: &ServerState, job: QueuedJob) {
+    let (result, retryable) = if job.is_abandoned() {
+        (
+            Err(anyhow::Error::msg(
+                "The job was abandoned by its worker too many times",
+            )),
+            false,
+        )
+    } else {
+        match serde_json::from_value::<Job>(job.payload.clone()) {
+            Ok(payload) => {
+                let state = state.clone();
+
+                let result = tokio::task::spawn_blocking(move || payload.run(&state))
+                    .await
+                    .map_err(anyhow::Error::from)
+                    .and_then(|result| result);
+
+                (result, true)
+            }
+            Err(err) => (Err(err.into()), false),
+        }
+    };
+
+    let kind = job.kind.clone();
+    let pgconnection = state.pgconnection.clone();
+
+    let outcome = tokio::task::spawn_blocking(move || match result {
+        Ok(()) => complete_job(&job, pgconnection)
+            .map(|removed_jobs| (removed_jobs != 0).then_some("succeeded")),
+        Err(err) => {
+            tracing::warn!(%err, "Job failed");
+
+            fail_job(&job, &err.to_string(), retryable, pgconnection).map(|status| {
+                status.map(|status| match status {
+                    JobStatus::Dead => "dead",
+                    JobStatus::Pending | JobStatus::Running => "retried",
+                })
+            })
+        }
+    })
+    .await;
+
+    match outcome {
+        Ok(Ok(Some(outcome))) => {
+            metrics::counter!("jobs_total", "kind" => kind, "outcome" => outcome).increment(1)
+        }
+        // The lock of the job has timed out while it was running, its result is recorded by the worker which has claimed it since
+        Ok(Ok(None)) => {
+            tracing::warn!("The job was claimed by another worker while it was running")
+        }
+        // The job is still marked as running, so it is claimed again once its lock times out
+        _ => tracing::warn!("Failed to record the result of the job"),
+    }
+}
+
+/// This function enqueues the scheduled jobs when they are due, until the server starts shutting down.
+async fn run_scheduler(state: ServerState) {
+    let mut interval = tokio::time::interval(SCHEDULER_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {},
+            _ = state.shutdown.cancelled() => break,
+        }
+
+        let pgconnection = state.pgconnection.clone();
+
+        // The schedules are only moved when their jobs get enqueued, so a failed check is made up for by the next one
+        let _ = tokio::task::spawn_blocking(move || enqueue_due_scheduled_jobs(pgconnection)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use diesel::r2d2::ConnectionManager;
+    use diesel_migrations::MigrationHarness;
+
+    use super::*;
+    use crate::lifecycle::MIGRATIONS;
+
+    /// This function creates a running job which has been claimed ```attempts``` times.
+    fn queued_job(attempts: i32, max_attempts: i32) -> QueuedJob {
+        let now = Utc::now().naive_utc();
+
+        QueuedJob {
+            id: 1,
+            kind: Job::PruneAuditEvents.kind().to_string(),
+            payload: serde_json::to_value(Job::PruneAuditEvents).unwrap(),
+            status: JobStatus::Running,
+            attempts,
+            max_attempts,
+            run_at: now,
+            locked_at: Some(now),
+            last_error: None,
+            created_at: now,
+        }
+    }
+
+    /// This function creates the time of the day passed in on 2024-05-01.
+    fn time(hour: u32, minute: u32, second: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_opt(hour, minute, second)
+            .unwrap()
+    }
+
+    #[test]
+    fn retry_delay_doubles_until_the_cap() {
+        let delays: Vec<i64> = (1..=10).map(retry_delay_seconds).collect();
+
+        assert_eq!(
+            delays,
+            [
+                10,
+                20,
+                40,
+                80,
+                160,
+                320,
+                640,
+                1280,
+                2560,
+                MAX_RETRY_DELAY_SECONDS
+            ]
+        );
+        assert_eq!(retry_delay_seconds(0), BASE_RETRY_DELAY_SECONDS);
+        assert_eq!(retry_delay_seconds(-1), BASE_RETRY_DELAY_SECONDS);
+        assert_eq!(retry_delay_seconds(64), MAX_RETRY_DELAY_SECONDS);
+        assert_eq!(retry_delay_seconds(i32::MAX), MAX_RETRY_DELAY_SECONDS);
+    }
+
+    #[test]
+    fn scheduled_job_runs_at_the_next_matching_time() {
+        let prune_audit_events = &SCHEDULED_JOBS[0];
+
+        assert_eq!(
+            prune_audit_events.next_run_after(time(2, 59, 59)).unwrap(),
+            time(3, 0, 0)
+        );
+        // The time passed in is not a run of its own
+        assert_eq!(
+            prune_audit_events.next_run_after(time(3, 0, 0)).unwrap(),
+            time(3, 0, 0) + chrono::Duration::days(1)
+        );
+        assert_eq!(
+            prune_audit_events.next_run_after(time(12, 0, 0)).unwrap(),
+            time(3, 0, 0) + chrono::Duration::days(1)
+        );
+    }
+
+    #[test]
+    fn every_schedule_is_valid() {
+        for scheduled_job in &SCHEDULED_JOBS {
+            assert!(scheduled_job.next_run_after(time(0, 0, 0)).is_ok());
+            assert_eq!((scheduled_job.job)().kind(), scheduled_job.name);
+        }
+
+        let invalid_job = ScheduledJob {
+            name: "invalid",
+            cron: "0 0 25 * * *",
+            job: || Job::PruneAuditEvents,
+        };
+
+        assert!(invalid_job.next_run_after(time(0, 0, 0)).is_err());
+    }
+
+    #[test]
+    fn failed_job_is_retried_until_it_uses_up_its_attempts() {
+        assert_eq!(
+            queued_job(1, 3).status_after_failure(true),
+            JobStatus::Pending
+        );
+        assert_eq!(
+            queued_job(2, 3).status_after_failure(true),
+            JobStatus::Pending
+        );
+        assert_eq!(queued_job(3, 3).status_after_failure(true), JobStatus::Dead);
+        // Jobs with an unrecognized payload are not retried
+        assert_eq!(
+            queued_job(1, 3).status_after_failure(false),
+            JobStatus::Dead
+        );
+    }
+
+    #[test]
+    fn abandoned_job_goes_dead() {
+        assert!(!queued_job(3, 3).is_abandoned());
+
+        let abandoned_job = queued_job(4, 3);
+
+        assert!(abandoned_job.is_abandoned());
+        assert_eq!(abandoned_job.status_after_failure(false), JobStatus::Dead);
+        assert_eq!(abandoned_job.status_after_failure(true), JobStatus::Dead);
+    }
+
+    /// This function marks the job as claimed by a worker at the time passed in, the same way ```claim_job``` does.
+    fn claim_job_at(job_id: i32, locked_at: NaiveDateTime, pgconnection: &PgPool) -> QueuedJob {
+        diesel::update(jobs::table.find(job_id))
+            .set((
+                jobs::status.eq(JobStatus::Running),
+                jobs::locked_at.eq(locked_at),
+                jobs::attempts.eq(jobs::attempts + 1),
+            ))
+            .returning(QueuedJob::as_returning())
+            .get_result(&mut pgconnection.get().unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    #[ignore = "needs a PostgreSQL database, its URL is read from the TEST_DATABASE_URL environment variable"]
+    fn lost_claim_is_left_to_the_new_worker() {
+        let database_url =
+            std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is not set");
+        let pgconnection: PgPool = r2d2::Builder::new()
+            .max_size(1)
+            .build(ConnectionManager::<PgConnection>::new(database_url))
+            .unwrap();
+
+        pgconnection
+            .get()
+            .unwrap()
+            .run_pending_migrations(MIGRATIONS)
+            .unwrap();
+
+        let job_id = enqueue_job(&Job::PruneAuditEvents, pgconnection.clone()).unwrap();
+
+        let now = Utc::now().naive_utc();
+
+        // The first worker's lock has timed out, so a second worker has claimed the job too
+        let first_claim = claim_job_at(
+            job_id,
+            now - chrono::Duration::seconds(LOCK_TIMEOUT_SECONDS.into()),
+            &pgconnection,
+        );
+        let second_claim = claim_job_at(job_id, now, &pgconnection);
+
+        assert_eq!(complete_job(&first_claim, pgconnection.clone()).unwrap(), 0);
+        assert_eq!(
+            fail_job(&first_claim, "error", true, pgconnection.clone()).unwrap(),
+            None
+        );
+
+        let status: JobStatus = jobs::table
+            .find(job_id)
+            .select(jobs::status)
+            .first(&mut pgconnection.get().unwrap())
+            .unwrap();
+
+        assert_eq!(status, JobStatus::Running);
+        assert_eq!(
+            complete_job(&second_claim, pgconnection.clone()).unwrap(),
+            1
+        );
+    }
+}
@@ -3,63 +3,145 @@ use argon2::{
     password_hash::{rand_core::OsRng, SaltString},
     Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
 };
+use auth::{AuthenticatedAccount, AuthenticationRejection, ClientInfo};
 use axum::{
-    extract::{Request, State}, http::HeaderMap, middleware::Next, response::{IntoResponse, Redirect}, Json
+    extract::{
+        ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        Path, Query, Request, State,
+    },
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Redirect, Response},
+    Json,
 };
 use axum_extra::extract::{cookie::Cookie, CookieJar};
+use cache::{CachedJson, ResponseCache};
 use db_types::{
-    safe_types::AccountLookup,
+    safe_types::{
+        AccountActionRequest, AccountLookup, AccountSearchQuery, AdminAccountLookup, AuditEvent,
+        AuditEventQuery, AuditEventType, AuditOutcome, BlockedAccount, Category, CategoryAttribute,
+        ExchangeRateEntry, ExchangeRateRequest, ModerationAction, ModerationLogEntry,
+        ModerationRequest, NewAuditEvent, NewCategory, NewCategoryAttribute, NewModerationLogEntry,
+        NewNotification, NewReport, NewSavedSearch, Notification, NotificationList, PageRequest,
+        Permission, Report, ReportRequest, ReportStatus, RoleRequest, SavedSearch,
+        SavedSearchRequest, SiteStatistics, Suggestion, SuggestionQuery, TargetKind,
+    },
     unsafe_types::{self, Account, AuthorizedUser},
 };
 use diesel::{
-    dsl::insert_into, r2d2::ConnectionManager, Connection, ExpressionMethods, OptionalExtension,
-    PgConnection, QueryDsl, RunQueryDsl, SelectableHelper,
+    dsl::insert_into, pg::expression::extensions::IntervalDsl, r2d2::ConnectionManager,
+    BoolExpressionMethods, Connection, ExpressionMethods, JoinOnDsl, NullableExpressionMethods,
+    OptionalExtension, PgConnection, PgTextExpressionMethods, QueryDsl, RunQueryDsl,
+    SelectableHelper,
 };
 use hmac::{Hmac, Mac};
 use jwt::{SignWithKey, VerifyWithKey};
+use money::CurrencyCode;
 use reqwest::StatusCode;
 use safe_functions::{
-    check_authenticated_account, handle_account_login_request, handle_account_register_request,
-    lookup_account_from_id, record_authenticated_account,
+    block_account, check_authenticated_account, count_unread_notifications, create_category,
+    create_category_attribute, create_report, create_saved_search, delete_category,
+    delete_category_attribute, delete_saved_search, grant_role, handle_account_login_request,
+    handle_account_register_request, insert_notification, is_account_blocked, list_audit_events,
+    list_blocked_accounts, list_moderation_log, list_notifications, list_pending_reports,
+    list_saved_searches, lookup_account_permissions, lookup_search_suggestions,
+    lookup_site_statistics, mark_notifications_read, moderate_target, record_audit_event,
+    record_authenticated_account, revoke_role, search_accounts, set_exchange_rate, unblock_account,
+    update_saved_search,
 };
 use schema::{
+    account_blocks, account_roles,
     accounts::{self, username},
+    audit_events,
     authorized_users::{self, session_id},
+    categories, category_attributes, exchange_rates, moderation_log, notifications, reports,
+    role_permissions, roles, saved_searches,
 };
 use sha2::Sha256;
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, time::Instant};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_util::sync::CancellationToken;
+use tracing::instrument;
 
+pub mod auth;
+pub mod cache;
+pub mod csrf;
+pub mod jobs;
+pub mod lifecycle;
+pub mod money;
+pub mod openapi;
 pub mod schema;
+pub mod security;
+pub mod spa;
+pub mod telemetry;
+pub mod tls;
 
 pub type PgPool = r2d2::Pool<ConnectionManager<PgConnection>>;
 
+/// The number of distinct accounts which have to report the same target before it gets hidden automatically
+pub const REPORT_HIDE_THRESHOLD: i64 = 3;
+
+/// The number of hours a target stays hidden for after it got hidden automatically
+pub const AUTO_HIDE_HOURS: i32 = 72;
+
+/// The number of days the events are kept in the audit log for
+pub const AUDIT_EVENT_RETENTION_DAYS: i32 = 365;
+
 #[derive(Clone)]
 pub struct ServerState {
     pub pgconnection: PgPool,
+    /// Every ```Notification``` sent via ```send_notification``` is broadcasted on this channel, so that they can be pushed to the live connections of the receiving account.
+    pub notification_sender: broadcast::Sender<Notification>,
+    /// This token is cancelled when the server starts shutting down, long-lived connections (e.g. the notification WebSockets) should close when it is.
+    pub shutdown: CancellationToken,
+    /// The cached responses of the public read endpoints, the handlers changing the underlying rows have to invalidate them.
+    pub response_cache: ResponseCache,
 }
 
 pub mod db_types {
     use std::fmt::Display;
 
+    use std::io::Write;
+
     use diesel::{
-        prelude::{Insertable, Queryable, QueryableByName},
+        deserialize::{self, FromSql, FromSqlRow},
+        expression::AsExpression,
+        pg::{Pg, PgValue},
+        prelude::{AsChangeset, Insertable, Queryable, QueryableByName},
+        serialize::{self, IsNull, Output, ToSql},
+        sql_types::Text,
         Selectable,
     };
     use serde::{Deserialize, Serialize};
+    use utoipa::{IntoParams, ToSchema};
 
     use crate::{
         hash_password,
         schema::{
-            accounts,
+            accounts, audit_events,
             authorized_users::{self},
+            categories, category_attributes, exchange_rates, moderation_log, notifications,
+            reports, saved_searches,
         },
     };
 
     pub mod unsafe_types {
+        use std::fmt::Debug;
+
         use crate::db_types::*;
 
+        /// The placeholder the ```Debug``` implementations print instead of secrets.
+        const REDACTED: &str = "<redacted>";
+
         #[derive(
-            QueryableByName, Selectable, Queryable, Insertable, Deserialize, Serialize, Clone, Debug,
+            QueryableByName,
+            Selectable,
+            Queryable,
+            Insertable,
+            Deserialize,
+            Serialize,
+            Clone,
+            ToSchema,
         )]
         #[diesel(check_for_backend(diesel::pg::Pg))]
         #[diesel(table_name = accounts)]
@@ -91,7 +173,17 @@ pub mod db_types {
             }
         }
 
-        #[derive(Queryable, Selectable, QueryableByName, Serialize, Deserialize, Clone, Debug)]
+        // The password is redacted, so that it never ends up in the logs
+        impl Debug for Account {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_struct("Account")
+                    .field("username", &self.username)
+                    .field("passw", &REDACTED)
+                    .finish()
+            }
+        }
+
+        #[derive(Queryable, Selectable, QueryableByName, Serialize, Deserialize, Clone)]
         #[diesel(check_for_backend(diesel::pg::Pg))]
         #[diesel(table_name = accounts)]
         /// This struct is used when returning ```Account``` instances from the database.
@@ -114,16 +206,20 @@ pub mod db_types {
             }
         }
 
+        // The password hash is redacted, so that it never ends up in the logs
+        impl Debug for AccountLookup {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_struct("AccountLookup")
+                    .field("username", &self.username)
+                    .field("id", &self.id)
+                    .field("passw", &REDACTED)
+                    .field("created_at", &self.created_at)
+                    .finish()
+            }
+        }
+
         #[derive(
-            QueryableByName,
-            Selectable,
-            Queryable,
-            Insertable,
-            Deserialize,
-            Serialize,
-            Clone,
-            Debug,
-            Default,
+            QueryableByName, Selectable, Queryable, Insertable, Deserialize, Serialize, Clone, Default,
         )]
         #[diesel(check_for_backend(diesel::pg::Pg))]
         #[diesel(table_name = authorized_users)]
@@ -158,12 +254,25 @@ pub mod db_types {
                 f.write_str(&serde_json::to_string(self).unwrap())
             }
         }
+
+        // The session is redacted, as it is the value of the `session_id` cookie, so it must never end up in the logs
+        impl Debug for AuthorizedUser {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_struct("AuthorizedUser")
+                    .field("client_signature", &REDACTED)
+                    .field("session_id", &REDACTED)
+                    .field("account_id", &self.account_id)
+                    .finish()
+            }
+        }
     }
 
     pub mod safe_types {
         use crate::db_types::*;
 
-        #[derive(Queryable, Selectable, QueryableByName, Serialize, Deserialize, Clone, Debug)]
+        #[derive(
+            Queryable, Selectable, QueryableByName, Serialize, Deserialize, Clone, Debug, ToSchema,
+        )]
         #[diesel(check_for_backend(diesel::pg::Pg))]
         #[diesel(table_name = accounts)]
         pub struct AccountLookup {
@@ -180,234 +289,2551 @@ pub mod db_types {
                 f.write_str(&serde_json::to_string(self).unwrap())
             }
         }
-    }
-}
 
-/// This function takes a password argument which it hashes with ```Argon2``` via the default hasher settings.
-pub fn hash_password(password: &str) -> anyhow::Result<String> {
-    //Create argon2 hasher instance
-    let argon2 = argon2::Argon2::default();
+        #[derive(
+            AsExpression,
+            FromSqlRow,
+            Serialize,
+            Deserialize,
+            Clone,
+            Copy,
+            Debug,
+            PartialEq,
+            Eq,
+            Default,
+            ToSchema,
+        )]
+        #[diesel(sql_type = diesel::sql_types::Text)]
+        #[serde(rename_all = "snake_case")]
+        /// This enum describes how often the owner of a ```SavedSearch``` wants to be alerted about new matches.
+        /// It is stored as text in the database.
+        /// Please note that no alerts are sent yet, as there are no listings to match the searches against, the frequency is only stored so that the alert job can use it once listings exist.
+        pub enum AlertFrequency {
+            /// Alert the owner as soon as a new match is found
+            Instant,
+            /// Collect the new matches and alert the owner once a day
+            #[default]
+            Daily,
+            /// Collect the new matches and alert the owner once a week
+            Weekly,
+            /// Never alert the owner, the search is only kept for quick access
+            Never,
+        }
 
-    //Create salt
-    let salt = SaltString::generate(&mut OsRng);
+        impl AlertFrequency {
+            /// This function returns the text representation of this ```AlertFrequency```, which is used when storing it in the database.
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    AlertFrequency::Instant => "instant",
+                    AlertFrequency::Daily => "daily",
+                    AlertFrequency::Weekly => "weekly",
+                    AlertFrequency::Never => "never",
+                }
+            }
+        }
 
-    Ok(argon2
-        .hash_password(password.as_bytes(), &salt)
-        .map_err(|err| anyhow::Error::msg(err.to_string()))?
-        .to_string())
-}
+        impl ToSql<Text, Pg> for AlertFrequency {
+            fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+                out.write_all(self.as_str().as_bytes())?;
 
-/// This function establishes the ```ServerState``` instance
-pub fn establish_server_state() -> anyhow::Result<ServerState> {
-    let database_url = include_str!("..\\..\\.env");
+                Ok(IsNull::No)
+            }
+        }
 
-    let connection_manager = ConnectionManager::new(database_url);
+        impl FromSql<Text, Pg> for AlertFrequency {
+            fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+                match bytes.as_bytes() {
+                    b"instant" => Ok(AlertFrequency::Instant),
+                    b"daily" => Ok(AlertFrequency::Daily),
+                    b"weekly" => Ok(AlertFrequency::Weekly),
+                    b"never" => Ok(AlertFrequency::Never),
+                    _ => Err("Unrecognized alert frequency".into()),
+                }
+            }
+        }
 
-    let pool = r2d2::Builder::new().build(connection_manager)?;
+        #[derive(Deserialize, Serialize, Clone, Debug, ToSchema)]
+        /// This struct is used when there are incoming requests from clients to save or update a search.
+        /// Every filter besides the query text is optional.
+        pub struct SavedSearchRequest {
+            /// The text the user has entered into the search bar
+            pub query: String,
+            /// The category the search is narrowed to
+            pub category: Option<String>,
+            /// The lowest price a listing can have to match
+            pub min_price: Option<i32>,
+            /// The highest price a listing can have to match
+            pub max_price: Option<i32>,
+            /// The location the search is narrowed to
+            pub location: Option<String>,
+            /// How often the owner wants to be alerted about new matches
+            #[serde(default)]
+            pub alert_frequency: AlertFrequency,
+        }
 
-    Ok(ServerState { pgconnection: pool })
-}
+        impl SavedSearchRequest {
+            /// This function checks whether the request is sensible to be stored.
+            /// It will return ```false``` if the query is empty or the price range is inverted.
+            pub fn is_valid(&self) -> bool {
+                if self.query.trim().is_empty() {
+                    return false;
+                }
 
-/// This mod contains `unsafe` function which **will** reveal sensitive information.
-/// These functions should **ONLY** be used in the backend where data security is verified and guaranteed.
-/// The `safe_functions` and `unsafe_functions` mod contains functions which make queries to the database.
-pub mod unsafe_functions {
-    use crate::*;
+                match (self.min_price, self.max_price) {
+                    (Some(min_price), Some(max_price)) => min_price <= max_price,
+                    _ => true,
+                }
+            }
+        }
 
-    /// This function looks up the full account information and returns an ```unsafe_types::AccountLookup``` instance.
-    /// Please note that this function should **NEVER** be used to return data to anywhere other then backend.
-    pub fn __lookup_account_from_id_unsafe(
-        id: i32,
-        pgconnection: PgPool,
-    ) -> anyhow::Result<unsafe_types::AccountLookup> {
-        pgconnection
-            .get()?
-            .build_transaction()
-            .read_only()
-            .run(move |conn| {
-                conn.transaction(|conn| {
-                    let matched_account: Option<unsafe_types::AccountLookup> =
-                        accounts::dsl::accounts
-                            .filter(accounts::dsl::id.eq(id))
-                            .first::<unsafe_types::AccountLookup>(conn)
-                            .ok();
+        #[derive(Insertable, AsChangeset, Clone, Debug)]
+        #[diesel(check_for_backend(diesel::pg::Pg))]
+        #[diesel(table_name = saved_searches)]
+        #[diesel(treat_none_as_null = true)]
+        /// This struct is used when writing a ```SavedSearchRequest``` to the database.
+        /// It pairs the request with the account it belongs to.
+        pub struct NewSavedSearch {
+            /// The UUID of the account which owns this search
+            pub account_id: i32,
+            /// The text the user has entered into the search bar
+            pub query: String,
+            /// The category the search is narrowed to
+            pub category: Option<String>,
+            /// The lowest price a listing can have to match
+            pub min_price: Option<i32>,
+            /// The highest price a listing can have to match
+            pub max_price: Option<i32>,
+            /// The location the search is narrowed to
+            pub location: Option<String>,
+            /// How often the owner wants to be alerted about new matches
+            pub alert_frequency: AlertFrequency,
+        }
 
-                    matched_account.ok_or_else(|| anyhow::Error::msg("Profile not found"))
-                })
-            })
-            .map_err(anyhow::Error::from)
-    }
-}
+        impl NewSavedSearch {
+            /// This function takes a ```SavedSearchRequest``` and the id of the account which made it and turns it into an insertable ```NewSavedSearch``` instance.
+            pub fn from_request(account_id: i32, request: SavedSearchRequest) -> Self {
+                Self {
+                    account_id,
+                    query: request.query.trim().to_string(),
+                    category: request.category,
+                    min_price: request.min_price,
+                    max_price: request.max_price,
+                    location: request.location,
+                    alert_frequency: request.alert_frequency,
+                }
+            }
+        }
 
-/// This mod of functions contain `safe` functions which can not reveal sensitive information.
-/// When creating a function in this mod please verify the intergrity.
-/// The `safe_functions` and `unsafe_functions` mod contains functions which make queries to the database.
-pub mod safe_functions {
-    use crate::*;
-    /// This function looks up the public information of an account based on their UUID.
-    /// This function will return an error if the user doesnt exist.
-    pub fn lookup_account_from_id(id: i32, pgconnection: PgPool) -> anyhow::Result<AccountLookup> {
-        pgconnection
-            .get()?
-            .build_transaction()
-            .read_only()
-            .run(move |conn| {
-                conn.transaction(|conn| {
-                    let matched_account: Option<AccountLookup> = accounts::dsl::accounts
-                        .filter(accounts::dsl::id.eq(id))
-                        .select(AccountLookup::as_select())
-                        .first(conn)
-                        .ok();
+        #[derive(
+            Queryable, Selectable, QueryableByName, Serialize, Deserialize, Clone, Debug, ToSchema,
+        )]
+        #[diesel(check_for_backend(diesel::pg::Pg))]
+        #[diesel(table_name = saved_searches)]
+        /// This struct is used when returning ```SavedSearch``` instances from the database.
+        /// These should only be returned to the account which owns them.
+        pub struct SavedSearch {
+            /// The id of the saved search
+            pub id: i32,
+            /// The UUID of the account which owns this search
+            pub account_id: i32,
+            /// The text the user has entered into the search bar
+            pub query: String,
+            /// The category the search is narrowed to
+            pub category: Option<String>,
+            /// The lowest price a listing can have to match
+            pub min_price: Option<i32>,
+            /// The highest price a listing can have to match
+            pub max_price: Option<i32>,
+            /// The location the search is narrowed to
+            pub location: Option<String>,
+            /// How often the owner wants to be alerted about new matches
+            pub alert_frequency: AlertFrequency,
+            /// The timestamp taken when the owner was last alerted about new matches
+            pub last_alerted_at: Option<chrono::NaiveDateTime>,
+            /// The timestamp taken when the search was saved
+            pub created_at: chrono::NaiveDateTime,
+        }
 
-                    matched_account.ok_or_else(|| anyhow::Error::msg("Profile not found"))
-                })
-            })
-            .map_err(anyhow::Error::from)
-    }
+        #[derive(Insertable, Deserialize, Serialize, Clone, Debug)]
+        #[diesel(check_for_backend(diesel::pg::Pg))]
+        #[diesel(table_name = notifications)]
+        /// This struct is used when a backend subsystem wants to notify an account about something.
+        /// It should be passed to ```send_notification```, which stores it and pushes it to the account's live connections.
+        pub struct NewNotification {
+            /// The UUID of the account which should receive this notification
+            pub account_id: i32,
+            /// The kind of the notification, this is set by the subsystem producing it (e.g. `saved_search_match`)
+            pub kind: String,
+            /// The title of the notification
+            pub title: String,
+            /// The body of the notification
+            pub body: String,
+            /// The frontend path the notification should lead to when clicked (This field is optional)
+            pub link: Option<String>,
+        }
 
-    /// This function is going to write data to the database and return an ```anyhow::Result<usize>```
-    /// If the query was unsuccessful or didnt find the user it will return ```Ok(usize)```, with the inner value being the nuber of rows inserted.
-    /// If the query was successful and found the user the client requested it will return an ```Error(_)```
-    pub fn handle_account_register_request(
-        request: Account,
-        pgconnection: PgPool,
-        client_headers: HeaderMap,
-    ) -> anyhow::Result<usize> {
-        pgconnection
-            .get()?
-            .build_transaction()
-            .read_write()
-            .run(move |conn| {
-                if let Ok(Some(_)) = accounts::dsl::accounts
-                    .filter(username.eq(&request.username))
-                    .select(Account::as_select())
-                    .first::<Account>(conn)
-                    .optional()
-                {
-                    bail!("User already exists.")
-                } else {
-                    conn.transaction(|conn| {
-                        insert_into(accounts::table)
-                            .values(&request.into_storable())
-                            .execute(conn)
-                    })
-                    .map_err(anyhow::Error::from)
-                }
-            })
-    }
+        #[derive(
+            Queryable, Selectable, QueryableByName, Serialize, Deserialize, Clone, Debug, ToSchema,
+        )]
+        #[diesel(check_for_backend(diesel::pg::Pg))]
+        #[diesel(table_name = notifications)]
+        /// This struct is used when returning ```Notification``` instances from the database.
+        /// These should only be returned to the account which received them.
+        pub struct Notification {
+            /// The id of the notification
+            pub id: i32,
+            /// The UUID of the account which received this notification
+            pub account_id: i32,
+            /// The kind of the notification
+            pub kind: String,
+            /// The title of the notification
+            pub title: String,
+            /// The body of the notification
+            pub body: String,
+            /// The frontend path the notification should lead to when clicked
+            pub link: Option<String>,
+            /// The timestamp taken when the notification was read, this is ```None``` if it is unread
+            pub read_at: Option<chrono::NaiveDateTime>,
+            /// The timestamp taken when the notification was created
+            pub created_at: chrono::NaiveDateTime,
+        }
 
-    /// This function is going to read data out of the database and return an ```anyhow::Result<Option<Account>>```
-    /// If the query was unsuccessful or didnt find the user it will return an error.
-    /// If the query was successful and found the user the client requested it will return an ```Account```
-    pub fn handle_account_login_request(
-        request: Account,
-        pgconnection: PgPool,
-    ) -> anyhow::Result<unsafe_types::AccountLookup> {
-        let argon2 = Argon2::default();
+        impl Display for Notification {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(&serde_json::to_string(self).unwrap())
+            }
+        }
 
-        pgconnection
-            .get()?
-            .build_transaction()
-            .read_only()
-            .run(|conn| {
-                conn.transaction(|conn| {
-                    let matched_account: Option<unsafe_types::AccountLookup> =
-                        accounts::dsl::accounts
-                            //Check for username match
-                            .filter(username.eq(request.username))
-                            .select(unsafe_types::AccountLookup::as_select())
-                            //Check for password match
-                            .load(conn)?
-                            .into_iter()
-                            .find(|account_lookup| {
-                                argon2
-                                    .verify_password(
-                                        request.passw.as_bytes(),
-                                        &PasswordHash::new(&account_lookup.passw).unwrap(),
-                                    )
-                                    .is_ok()
-                            });
+        #[derive(Deserialize, Serialize, Clone, Debug, IntoParams)]
+        #[into_params(parameter_in = Query)]
+        /// This struct is used when a client requests a page of a list (e.g. their notifications).
+        pub struct PageRequest {
+            /// The index of the requested page, starting from 0
+            #[serde(default)]
+            pub page: i64,
+            /// The number of entries on a page, this is capped at ```PageRequest::MAX_PER_PAGE```
+            #[serde(default = "PageRequest::default_per_page")]
+            pub per_page: i64,
+        }
 
-                    matched_account.ok_or_else(|| anyhow::Error::msg("Profile not found"))
-                })
-            })
-            .map_err(anyhow::Error::from)
-    }
+        impl PageRequest {
+            /// The maximum number of entries which can be requested at once
+            pub const MAX_PER_PAGE: i64 = 100;
 
-    /// This function takes an ```&AuthorizedUser``` instance which it writes to the database, so that it can be accessed later to authenticate the user
-    pub fn record_authenticated_account(
-        authorized_user: &AuthorizedUser,
-        pgconnection: PgPool,
-    ) -> anyhow::Result<usize> {
-        pgconnection
-            .get()?
-            .build_transaction()
-            .read_write()
-            .run(move |conn| {
-                conn.transaction(|conn| {
-                    insert_into(authorized_users::table)
-                        .values(authorized_user)
-                        .execute(conn)
-                })
-                .map_err(anyhow::Error::from)
-            })
-            .map_err(anyhow::Error::from)
-    }
+            fn default_per_page() -> i64 {
+                20
+            }
 
-    /// This function takes an ```&AuthorizedUser``` instance which it check for in the database, so it authenticate the user
-    pub fn check_authenticated_account(
-        pgconnection: PgPool,
-        authorized_user: &AuthorizedUser,
-    ) -> Result<Option<AuthorizedUser>, StatusCode> {
-        pgconnection
-            .get()
-            .map_err(|_| StatusCode::REQUEST_TIMEOUT)?
-            .build_transaction()
-            .read_only()
-            .run(|conn| {
-                conn.transaction(|conn| {
-                    let matched_authorized_account = authorized_users::dsl::authorized_users
-                        .filter(session_id.eq(authorized_user.session_id.clone()))
-                        .select(AuthorizedUser::as_select())
-                        .load(conn)?
-                        .into_iter()
-                        .find(|auth_user| {
+            /// This function returns the ```(limit, offset)``` pair of this page, clamped to sensible values.
+            /// The offset saturates instead of overflowing, so a page beyond the end of the list is just empty.
+            pub fn limit_offset(&self) -> (i64, i64) {
+                let per_page = self.per_page.clamp(1, Self::MAX_PER_PAGE);
+
+                (per_page, self.page.max(0).saturating_mul(per_page))
+            }
+        }
+
+        #[derive(Deserialize, Serialize, Clone, Debug, ToSchema)]
+        /// This struct is returned when a client requests a page of their notifications.
+        pub struct NotificationList {
+            /// The notifications on the requested page, the newest one first
+            pub notifications: Vec<Notification>,
+            /// The number of unread notifications the account has in total
+            pub unread_count: i64,
+        }
+
+        #[derive(
+            Queryable, Selectable, QueryableByName, Serialize, Deserialize, Clone, Debug, ToSchema,
+        )]
+        #[diesel(check_for_backend(diesel::pg::Pg))]
+        #[diesel(table_name = categories)]
+        /// This struct is used when returning ```Category``` instances from the database.
+        pub struct Category {
+            /// The id of the category
+            pub id: i32,
+            /// The displayed name of the category
+            pub name: String,
+            /// The id of the category this one is nested in, this is ```None``` for top level categories
+            pub parent_id: Option<i32>,
+        }
+
+        #[derive(
+            AsExpression,
+            FromSqlRow,
+            Serialize,
+            Deserialize,
+            Clone,
+            Copy,
+            Debug,
+            PartialEq,
+            Eq,
+            ToSchema,
+        )]
+        #[diesel(sql_type = diesel::sql_types::Text)]
+        #[serde(rename_all = "snake_case")]
+        /// This enum describes what kind of value a ```CategoryAttribute``` accepts.
+        /// It is stored as text in the database.
+        pub enum AttributeType {
+            /// A whole number (e.g. the year a car was made in)
+            Integer,
+            /// Any number (e.g. the screen size of a phone)
+            Number,
+            /// Any text
+            Text,
+            /// A yes or no value
+            Boolean,
+        }
+
+        impl AttributeType {
+            /// This function returns the text representation of this ```AttributeType```, which is used when storing it in the database.
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    AttributeType::Integer => "integer",
+                    AttributeType::Number => "number",
+                    AttributeType::Text => "text",
+                    AttributeType::Boolean => "boolean",
+                }
+            }
+
+            /// This function checks whether the value passed in is of this ```AttributeType```.
+            pub fn matches(&self, value: &serde_json::Value) -> bool {
+                match self {
+                    AttributeType::Integer => value.is_i64() || value.is_u64(),
+                    AttributeType::Number => value.is_number(),
+                    AttributeType::Text => value.is_string(),
+                    AttributeType::Boolean => value.is_boolean(),
+                }
+            }
+        }
+
+        impl ToSql<Text, Pg> for AttributeType {
+            fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+                out.write_all(self.as_str().as_bytes())?;
+
+                Ok(IsNull::No)
+            }
+        }
+
+        impl FromSql<Text, Pg> for AttributeType {
+            fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+                match bytes.as_bytes() {
+                    b"integer" => Ok(AttributeType::Integer),
+                    b"number" => Ok(AttributeType::Number),
+                    b"text" => Ok(AttributeType::Text),
+                    b"boolean" => Ok(AttributeType::Boolean),
+                    _ => Err("Unrecognized attribute type".into()),
+                }
+            }
+        }
+
+        #[derive(
+            Queryable, Selectable, QueryableByName, Serialize, Deserialize, Clone, Debug, ToSchema,
+        )]
+        #[diesel(check_for_backend(diesel::pg::Pg))]
+        #[diesel(table_name = category_attributes)]
+        /// This struct is used when returning the attribute schema of a category from the database.
+        /// Every listing in the category should have its attributes validated against these.
+        pub struct CategoryAttribute {
+            /// The id of the attribute
+            pub id: i32,
+            /// The id of the category this attribute belongs to
+            pub category_id: i32,
+            /// The key the attribute is stored under (e.g. `mileage`)
+            pub name: String,
+            /// The displayed name of the attribute (e.g. `Kilométeróra állása`)
+            pub label: String,
+            /// The kind of value this attribute accepts
+            pub value_type: AttributeType,
+            /// The unit the value is measured in (e.g. `km`), this is only used for displaying
+            pub unit: Option<String>,
+            /// The list of values this attribute can take, if this is ```None``` any value of the correct type is accepted
+            pub allowed_values: Option<serde_json::Value>,
+            /// Whether the attribute has to be present on every listing in the category
+            pub required: bool,
+        }
+
+        #[derive(Deserialize, Serialize, Clone, Debug, IntoParams)]
+        #[into_params(parameter_in = Query)]
+        /// This struct is used when a client requests suggestions for the text entered into the search bar.
+        pub struct SuggestionQuery {
+            /// The text entered into the search bar so far
+            pub q: String,
+            /// The maximum number of suggestions returned, this is capped at ```SuggestionQuery::MAX_LIMIT```
+            #[serde(default = "SuggestionQuery::default_limit")]
+            pub limit: i64,
+        }
+
+        impl SuggestionQuery {
+            /// The maximum number of suggestions which can be requested at once
+            pub const MAX_LIMIT: i64 = 20;
+
+            /// The minimum number of characters needed before suggestions are looked up
+            pub const MIN_QUERY_LENGTH: usize = 2;
+
+            fn default_limit() -> i64 {
+                8
+            }
+        }
+
+        #[derive(QueryableByName, Clone, Debug)]
+        /// This struct is used when returning search suggestions from the database.
+        pub struct Suggestion {
+            /// The suggested text
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            pub suggestion: String,
+        }
+
+        #[derive(
+            Queryable, Selectable, QueryableByName, Serialize, Deserialize, Clone, Debug, ToSchema,
+        )]
+        #[diesel(check_for_backend(diesel::pg::Pg))]
+        #[diesel(table_name = exchange_rates)]
+        /// This struct is used when returning the exchange rate of a currency from the database.
+        /// Every price is normalized to forints with these when prices of different currencies are compared.
+        pub struct ExchangeRateEntry {
+            /// The currency this rate converts from
+            pub currency: crate::money::CurrencyCode,
+            /// The number of millionths of a forint one minor unit of the currency is worth (see ```money::EXCHANGE_RATE_SCALE```)
+            pub rate_to_huf: i64,
+            /// The timestamp taken when the rate was last updated
+            pub updated_at: chrono::NaiveDateTime,
+        }
+
+        #[derive(
+            AsExpression,
+            FromSqlRow,
+            Serialize,
+            Deserialize,
+            Clone,
+            Copy,
+            Debug,
+            PartialEq,
+            Eq,
+            ToSchema,
+        )]
+        #[diesel(sql_type = diesel::sql_types::Text)]
+        #[serde(rename_all = "snake_case")]
+        /// This enum describes what kind of entity a report or a moderation action targets.
+        /// It is stored as text in the database, the id of the target is stored next to it.
+        pub enum TargetKind {
+            /// The target is an account, the target id is the UUID of the account
+            Account,
+        }
+
+        impl TargetKind {
+            /// This function returns the text representation of this ```TargetKind```, which is used when storing it in the database.
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    TargetKind::Account => "account",
+                }
+            }
+        }
+
+        impl ToSql<Text, Pg> for TargetKind {
+            fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+                out.write_all(self.as_str().as_bytes())?;
+
+                Ok(IsNull::No)
+            }
+        }
+
+        impl FromSql<Text, Pg> for TargetKind {
+            fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+                match bytes.as_bytes() {
+                    b"account" => Ok(TargetKind::Account),
+                    _ => Err("Unrecognized target kind".into()),
+                }
+            }
+        }
+
+        #[derive(
+            AsExpression,
+            FromSqlRow,
+            Serialize,
+            Deserialize,
+            Clone,
+            Copy,
+            Debug,
+            PartialEq,
+            Eq,
+            ToSchema,
+        )]
+        #[diesel(sql_type = diesel::sql_types::Text)]
+        #[serde(rename_all = "snake_case")]
+        /// This enum describes why something got reported.
+        /// It is stored as text in the database.
+        pub enum ReportReason {
+            /// The target is trying to scam other users
+            Scam,
+            /// The target is selling something which is not allowed to be sold
+            ProhibitedItem,
+            /// The target is harassing other users
+            Harassment,
+            /// The target is spamming
+            Spam,
+            /// Any other reason, this should be explained in the details of the report
+            Other,
+        }
+
+        impl ReportReason {
+            /// This function returns the text representation of this ```ReportReason```, which is used when storing it in the database.
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    ReportReason::Scam => "scam",
+                    ReportReason::ProhibitedItem => "prohibited_item",
+                    ReportReason::Harassment => "harassment",
+                    ReportReason::Spam => "spam",
+                    ReportReason::Other => "other",
+                }
+            }
+        }
+
+        impl ToSql<Text, Pg> for ReportReason {
+            fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+                out.write_all(self.as_str().as_bytes())?;
+
+                Ok(IsNull::No)
+            }
+        }
+
+        impl FromSql<Text, Pg> for ReportReason {
+            fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+                match bytes.as_bytes() {
+                    b"scam" => Ok(ReportReason::Scam),
+                    b"prohibited_item" => Ok(ReportReason::ProhibitedItem),
+                    b"harassment" => Ok(ReportReason::Harassment),
+                    b"spam" => Ok(ReportReason::Spam),
+                    b"other" => Ok(ReportReason::Other),
+                    _ => Err("Unrecognized report reason".into()),
+                }
+            }
+        }
+
+        #[derive(
+            AsExpression,
+            FromSqlRow,
+            Serialize,
+            Deserialize,
+            Clone,
+            Copy,
+            Debug,
+            PartialEq,
+            Eq,
+            ToSchema,
+        )]
+        #[diesel(sql_type = diesel::sql_types::Text)]
+        #[serde(rename_all = "snake_case")]
+        /// This enum describes where a report is in the moderation queue.
+        /// It is stored as text in the database.
+        pub enum ReportStatus {
+            /// The report is waiting to be reviewed by a moderator
+            Pending,
+            /// A moderator has reviewed the report and took action against the target
+            Resolved,
+            /// A moderator has reviewed the report and found nothing to act on
+            Dismissed,
+        }
+
+        impl ReportStatus {
+            /// This function returns the text representation of this ```ReportStatus```, which is used when storing it in the database.
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    ReportStatus::Pending => "pending",
+                    ReportStatus::Resolved => "resolved",
+                    ReportStatus::Dismissed => "dismissed",
+                }
+            }
+        }
+
+        impl ToSql<Text, Pg> for ReportStatus {
+            fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+                out.write_all(self.as_str().as_bytes())?;
+
+                Ok(IsNull::No)
+            }
+        }
+
+        impl FromSql<Text, Pg> for ReportStatus {
+            fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+                match bytes.as_bytes() {
+                    b"pending" => Ok(ReportStatus::Pending),
+                    b"resolved" => Ok(ReportStatus::Resolved),
+                    b"dismissed" => Ok(ReportStatus::Dismissed),
+                    _ => Err("Unrecognized report status".into()),
+                }
+            }
+        }
+
+        #[derive(
+            AsExpression, FromSqlRow, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq,
+        )]
+        #[diesel(sql_type = diesel::sql_types::Text)]
+        #[serde(rename_all = "snake_case")]
+        /// This enum describes an action taken against a reported target.
+        /// It is stored as text in the database.
+        pub enum ModerationAction {
+            /// Hide the target until it gets restored
+            Hide,
+            /// Undo every previous hide and ban of the target
+            Restore,
+            /// Ban the target, for accounts this also ends every session of the account
+            Ban,
+            /// Close the pending reports against the target without acting on it
+            Dismiss,
+            /// The target was hidden temporarily because it got reported by too many accounts, this is only taken by the backend itself
+            AutoHide,
+            /// End every session of the target account without banning it
+            RevokeSessions,
+        }
+
+        impl ModerationAction {
+            /// This function returns the text representation of this ```ModerationAction```, which is used when storing it in the database.
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    ModerationAction::Hide => "hide",
+                    ModerationAction::Restore => "restore",
+                    ModerationAction::Ban => "ban",
+                    ModerationAction::Dismiss => "dismiss",
+                    ModerationAction::AutoHide => "auto_hide",
+                    ModerationAction::RevokeSessions => "revoke_sessions",
+                }
+            }
+        }
+
+        impl ToSql<Text, Pg> for ModerationAction {
+            fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+                out.write_all(self.as_str().as_bytes())?;
+
+                Ok(IsNull::No)
+            }
+        }
+
+        impl FromSql<Text, Pg> for ModerationAction {
+            fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+                match bytes.as_bytes() {
+                    b"hide" => Ok(ModerationAction::Hide),
+                    b"restore" => Ok(ModerationAction::Restore),
+                    b"ban" => Ok(ModerationAction::Ban),
+                    b"dismiss" => Ok(ModerationAction::Dismiss),
+                    b"auto_hide" => Ok(ModerationAction::AutoHide),
+                    b"revoke_sessions" => Ok(ModerationAction::RevokeSessions),
+                    _ => Err("Unrecognized moderation action".into()),
+                }
+            }
+        }
+
+        #[derive(Deserialize, Serialize, Clone, Debug, ToSchema)]
+        /// This struct is used when there are incoming requests from clients to report something.
+        pub struct ReportRequest {
+            /// The kind of the reported target
+            pub target_kind: TargetKind,
+            /// The id of the reported target
+            pub target_id: i32,
+            /// The reason of the report
+            pub reason: ReportReason,
+            /// The explanation of the reporter (This field is optional)
+            pub details: Option<String>,
+        }
+
+        #[derive(Insertable, Clone, Debug)]
+        #[diesel(check_for_backend(diesel::pg::Pg))]
+        #[diesel(table_name = reports)]
+        /// This struct is used when writing a ```ReportRequest``` to the database.
+        pub struct NewReport {
+            /// The UUID of the account which made the report
+            pub reporter_id: i32,
+            /// The kind of the reported target
+            pub target_kind: TargetKind,
+            /// The id of the reported target
+            pub target_id: i32,
+            /// The reason of the report
+            pub reason: ReportReason,
+            /// The explanation of the reporter
+            pub details: Option<String>,
+        }
+
+        #[derive(
+            Queryable, Selectable, QueryableByName, Serialize, Deserialize, Clone, Debug, ToSchema,
+        )]
+        #[diesel(check_for_backend(diesel::pg::Pg))]
+        #[diesel(table_name = reports)]
+        /// This struct is used when returning ```Report``` instances from the database.
+        /// These should only be returned to moderators.
+        pub struct Report {
+            /// The id of the report
+            pub id: i32,
+            /// The UUID of the account which made the report
+            pub reporter_id: i32,
+            /// The kind of the reported target
+            pub target_kind: TargetKind,
+            /// The id of the reported target
+            pub target_id: i32,
+            /// The reason of the report
+            pub reason: ReportReason,
+            /// The explanation of the reporter
+            pub details: Option<String>,
+            /// Where the report is in the moderation queue
+            pub status: ReportStatus,
+            /// The timestamp taken when the report was made
+            pub created_at: chrono::NaiveDateTime,
+            /// The UUID of the moderator who reviewed the report
+            pub resolved_by: Option<i32>,
+            /// The timestamp taken when the report was reviewed
+            pub resolved_at: Option<chrono::NaiveDateTime>,
+        }
+
+        #[derive(Deserialize, Serialize, Clone, Debug)]
+        /// This struct is used when a moderator acts on a target.
+        pub struct ModerationRequest {
+            /// The kind of the target
+            pub target_kind: TargetKind,
+            /// The id of the target
+            pub target_id: i32,
+            /// The action taken against the target
+            pub action: ModerationAction,
+            /// The moderator's note explaining the action (This field is optional)
+            pub note: Option<String>,
+        }
+
+        #[derive(Insertable, Clone, Debug)]
+        #[diesel(check_for_backend(diesel::pg::Pg))]
+        #[diesel(table_name = moderation_log)]
+        /// This struct is used when writing a moderation action to the audit log.
+        pub struct NewModerationLogEntry {
+            /// The UUID of the moderator who took the action, this is ```None``` for actions taken by the backend itself
+            pub moderator_id: Option<i32>,
+            /// The action taken
+            pub action: ModerationAction,
+            /// The kind of the target
+            pub target_kind: TargetKind,
+            /// The id of the target
+            pub target_id: i32,
+            /// The note explaining the action
+            pub note: Option<String>,
+        }
+
+        #[derive(Queryable, Selectable, QueryableByName, Serialize, Deserialize, Clone, Debug)]
+        #[diesel(check_for_backend(diesel::pg::Pg))]
+        #[diesel(table_name = moderation_log)]
+        /// This struct is used when returning entries of the moderation audit log from the database.
+        /// These should only be returned to moderators.
+        pub struct ModerationLogEntry {
+            /// The id of the entry
+            pub id: i32,
+            /// The UUID of the moderator who took the action, this is ```None``` for actions taken by the backend itself
+            pub moderator_id: Option<i32>,
+            /// The action taken
+            pub action: ModerationAction,
+            /// The kind of the target
+            pub target_kind: TargetKind,
+            /// The id of the target
+            pub target_id: i32,
+            /// The note explaining the action
+            pub note: Option<String>,
+            /// The timestamp taken when the action was taken
+            pub created_at: chrono::NaiveDateTime,
+        }
+
+        #[derive(
+            AsExpression,
+            FromSqlRow,
+            Serialize,
+            Deserialize,
+            Clone,
+            Copy,
+            Debug,
+            PartialEq,
+            Eq,
+            Hash,
+            ToSchema,
+        )]
+        #[diesel(sql_type = diesel::sql_types::Text)]
+        #[serde(rename_all = "snake_case")]
+        /// This enum describes what an account is allowed to do besides the actions every account can take.
+        /// Permissions are granted to roles, and roles are granted to accounts, it is stored as text in the database.
+        pub enum Permission {
+            /// The account can review the moderation queue and take moderation actions
+            ModerateReports,
+            /// The account can set the exchange rates prices are normalized with
+            ManageExchangeRates,
+            /// The account can grant roles to and revoke roles from other accounts
+            ManageRoles,
+            /// The account can search accounts, suspend them and revoke their sessions
+            ManageAccounts,
+            /// The account can create and delete categories and their attributes
+            ManageCategories,
+            /// The account can view the statistics of the site
+            ViewStatistics,
+            /// The account can view the audit log of authentication events and admin actions
+            ViewAuditLog,
+        }
+
+        impl Permission {
+            /// This function returns the text representation of this ```Permission```, which is used when storing it in the database.
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    Permission::ModerateReports => "moderate_reports",
+                    Permission::ManageExchangeRates => "manage_exchange_rates",
+                    Permission::ManageRoles => "manage_roles",
+                    Permission::ManageAccounts => "manage_accounts",
+                    Permission::ManageCategories => "manage_categories",
+                    Permission::ViewStatistics => "view_statistics",
+                    Permission::ViewAuditLog => "view_audit_log",
+                }
+            }
+        }
+
+        impl ToSql<Text, Pg> for Permission {
+            fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+                out.write_all(self.as_str().as_bytes())?;
+
+                Ok(IsNull::No)
+            }
+        }
+
+        impl FromSql<Text, Pg> for Permission {
+            fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+                match bytes.as_bytes() {
+                    b"moderate_reports" => Ok(Permission::ModerateReports),
+                    b"manage_exchange_rates" => Ok(Permission::ManageExchangeRates),
+                    b"manage_roles" => Ok(Permission::ManageRoles),
+                    b"manage_accounts" => Ok(Permission::ManageAccounts),
+                    b"manage_categories" => Ok(Permission::ManageCategories),
+                    b"view_statistics" => Ok(Permission::ViewStatistics),
+                    b"view_audit_log" => Ok(Permission::ViewAuditLog),
+                    _ => Err("Unrecognized permission".into()),
+                }
+            }
+        }
+
+        #[derive(Deserialize, Serialize, Clone, Debug)]
+        /// This struct is used when an admin grants a role to or revokes a role from an account.
+        pub struct RoleRequest {
+            /// The username of the account
+            pub username: String,
+            /// The name of the role (e.g. `moderator`)
+            pub role: String,
+        }
+
+        #[derive(Deserialize, Serialize, Clone, Debug)]
+        /// This struct is used when an admin sets the exchange rate of a currency.
+        pub struct ExchangeRateRequest {
+            /// The currency the rate converts from
+            pub currency: crate::money::CurrencyCode,
+            /// The number of millionths of a forint one minor unit of the currency is worth (see ```money::EXCHANGE_RATE_SCALE```)
+            pub rate_to_huf: i64,
+        }
+
+        #[derive(Deserialize, Serialize, Clone, Debug, Default)]
+        /// This struct is used when an admin searches the accounts, the page is requested with a separate ```PageRequest```.
+        pub struct AccountSearchQuery {
+            /// The text the username has to contain, every account matches if this is empty
+            #[serde(default)]
+            pub q: String,
+        }
+
+        #[derive(Queryable, Selectable, Serialize, Deserialize, Clone, Debug)]
+        #[diesel(check_for_backend(diesel::pg::Pg))]
+        #[diesel(table_name = accounts)]
+        /// This struct is used when returning accounts to admins, it contains the moderation state of the account besides its public information.
+        pub struct AdminAccountLookup {
+            /// The UUID of the account
+            pub id: i32,
+            /// The username of the account
+            pub username: String,
+            /// The timestamp taken when the account was created
+            pub created_at: chrono::NaiveDate,
+            /// The timestamp taken when the account got hidden, this is ```None``` if the account is visible
+            pub hidden_at: Option<chrono::NaiveDateTime>,
+            /// The timestamp the account stays hidden until, this is ```None``` if the account is hidden until it gets restored
+            pub hidden_until: Option<chrono::NaiveDateTime>,
+            /// The timestamp taken when the account got suspended, this is ```None``` if the account can log in
+            pub banned_at: Option<chrono::NaiveDateTime>,
+        }
+
+        #[derive(Deserialize, Serialize, Clone, Debug)]
+        /// This struct is used when an admin acts on an account (e.g. suspends it).
+        pub struct AccountActionRequest {
+            /// The UUID of the account
+            pub account_id: i32,
+            /// The admin's note explaining the action (This field is optional)
+            pub note: Option<String>,
+        }
+
+        #[derive(Insertable, Deserialize, Serialize, Clone, Debug)]
+        #[diesel(check_for_backend(diesel::pg::Pg))]
+        #[diesel(table_name = categories)]
+        /// This struct is used when an admin creates a new category.
+        pub struct NewCategory {
+            /// The displayed name of the category
+            pub name: String,
+            /// The id of the category this one is nested in (This field is optional)
+            pub parent_id: Option<i32>,
+        }
+
+        #[derive(Insertable, Deserialize, Serialize, Clone, Debug)]
+        #[diesel(check_for_backend(diesel::pg::Pg))]
+        #[diesel(table_name = category_attributes)]
+        /// This struct is used when an admin adds an attribute to the schema of a category.
+        pub struct NewCategoryAttribute {
+            /// The id of the category the attribute belongs to
+            pub category_id: i32,
+            /// The key the attribute is stored under (e.g. `mileage`)
+            pub name: String,
+            /// The displayed name of the attribute (e.g. `Kilométeróra állása`)
+            pub label: String,
+            /// The kind of value this attribute accepts
+            pub value_type: AttributeType,
+            /// The unit the value is measured in (This field is optional)
+            pub unit: Option<String>,
+            /// The list of values this attribute can take (This field is optional)
+            pub allowed_values: Option<serde_json::Value>,
+            /// Whether the attribute has to be present on every listing in the category
+            #[serde(default)]
+            pub required: bool,
+        }
+
+        impl NewCategoryAttribute {
+            /// This function checks whether the attribute can be added to the schema.
+            /// The key and the label must not be empty, and the allowed values must be a list of values of the attribute's type.
+            pub fn validate(&self) -> anyhow::Result<()> {
+                if self.name.trim().is_empty() || self.label.trim().is_empty() {
+                    anyhow::bail!("The name and the label of an attribute must not be empty.")
+                }
+
+                if let Some(allowed_values) = &self.allowed_values {
+                    let Some(allowed_values) = allowed_values.as_array() else {
+                        anyhow::bail!("The allowed values of an attribute must be a list.")
+                    };
+
+                    if !allowed_values
+                        .iter()
+                        .all(|value| self.value_type.matches(value))
+                    {
+                        anyhow::bail!(
+                            "Every allowed value must be of type `{}`.",
+                            self.value_type.as_str()
+                        )
+                    }
+                }
+
+                Ok(())
+            }
+        }
+
+        #[derive(Serialize, Deserialize, Clone, Debug, Default)]
+        /// This struct contains the statistics shown on the admin panel.
+        pub struct SiteStatistics {
+            /// The number of registered accounts
+            pub account_count: i64,
+            /// The number of accounts which are currently hidden
+            pub hidden_account_count: i64,
+            /// The number of suspended accounts
+            pub suspended_account_count: i64,
+            /// The number of sessions which are logged in
+            pub session_count: i64,
+            /// The number of reports waiting for a moderator
+            pub pending_report_count: i64,
+            /// The number of categories
+            pub category_count: i64,
+            /// The number of saved searches
+            pub saved_search_count: i64,
+        }
+
+        #[derive(
+            AsExpression, FromSqlRow, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq,
+        )]
+        #[diesel(sql_type = diesel::sql_types::Text)]
+        #[serde(rename_all = "snake_case")]
+        /// This enum describes the kind of a security relevant event recorded in the audit log.
+        /// It is stored as text in the database.
+        pub enum AuditEventType {
+            /// An account tried to log in
+            Login,
+            /// An account tried to register
+            Registration,
+            /// A `session_id` cookie was rejected because it is malformed or doesnt match a stored session
+            SessionRejected,
+            /// Every session of an account got ended by a moderator or an admin
+            SessionRevocation,
+            /// A moderator or an admin changed something through a guarded route
+            AdminAction,
+        }
+
+        impl AuditEventType {
+            /// This function returns the text representation of this ```AuditEventType```, which is used when storing it in the database.
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    AuditEventType::Login => "login",
+                    AuditEventType::Registration => "registration",
+                    AuditEventType::SessionRejected => "session_rejected",
+                    AuditEventType::SessionRevocation => "session_revocation",
+                    AuditEventType::AdminAction => "admin_action",
+                }
+            }
+        }
+
+        impl ToSql<Text, Pg> for AuditEventType {
+            fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+                out.write_all(self.as_str().as_bytes())?;
+
+                Ok(IsNull::No)
+            }
+        }
+
+        impl FromSql<Text, Pg> for AuditEventType {
+            fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+                match bytes.as_bytes() {
+                    b"login" => Ok(AuditEventType::Login),
+                    b"registration" => Ok(AuditEventType::Registration),
+                    b"session_rejected" => Ok(AuditEventType::SessionRejected),
+                    b"session_revocation" => Ok(AuditEventType::SessionRevocation),
+                    b"admin_action" => Ok(AuditEventType::AdminAction),
+                    _ => Err("Unrecognized audit event type".into()),
+                }
+            }
+        }
+
+        #[derive(
+            AsExpression, FromSqlRow, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq,
+        )]
+        #[diesel(sql_type = diesel::sql_types::Text)]
+        #[serde(rename_all = "snake_case")]
+        /// This enum describes whether the action recorded in the audit log succeeded.
+        /// It is stored as text in the database.
+        pub enum AuditOutcome {
+            Success,
+            Failure,
+        }
+
+        impl AuditOutcome {
+            /// This function returns the text representation of this ```AuditOutcome```, which is used when storing it in the database.
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    AuditOutcome::Success => "success",
+                    AuditOutcome::Failure => "failure",
+                }
+            }
+        }
+
+        impl ToSql<Text, Pg> for AuditOutcome {
+            fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+                out.write_all(self.as_str().as_bytes())?;
+
+                Ok(IsNull::No)
+            }
+        }
+
+        impl FromSql<Text, Pg> for AuditOutcome {
+            fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+                match bytes.as_bytes() {
+                    b"success" => Ok(AuditOutcome::Success),
+                    b"failure" => Ok(AuditOutcome::Failure),
+                    _ => Err("Unrecognized audit outcome".into()),
+                }
+            }
+        }
+
+        #[derive(Insertable, Clone, Debug)]
+        #[diesel(check_for_backend(diesel::pg::Pg))]
+        #[diesel(table_name = audit_events)]
+        /// This struct is used when writing an event to the audit log.
+        pub struct NewAuditEvent {
+            /// The UUID of the account the event is about, this is ```None``` if it is not known (e.g. a failed login)
+            pub account_id: Option<i32>,
+            /// The username the client has entered, this is only recorded for logins and registrations
+            pub username: Option<String>,
+            /// The IP address of the client
+            pub ip_address: Option<String>,
+            /// The `User-Agent` header sent by the client
+            pub user_agent: Option<String>,
+            /// The kind of the event
+            pub event_type: AuditEventType,
+            /// Whether the action succeeded
+            pub outcome: AuditOutcome,
+            /// Additional information about the event (e.g. the route an admin has used)
+            pub details: Option<String>,
+        }
+
+        impl NewAuditEvent {
+            /// This function creates a new ```NewAuditEvent``` made by the client passed in, the account, the username and the details are left empty.
+            pub fn from_client(
+                event_type: AuditEventType,
+                outcome: AuditOutcome,
+                client: &crate::auth::ClientInfo,
+            ) -> Self {
+                Self {
+                    account_id: None,
+                    username: None,
+                    ip_address: client.ip_address.clone(),
+                    user_agent: client.user_agent.clone(),
+                    event_type,
+                    outcome,
+                    details: None,
+                }
+            }
+        }
+
+        #[derive(Queryable, Selectable, Serialize, Deserialize, Clone, Debug)]
+        #[diesel(check_for_backend(diesel::pg::Pg))]
+        #[diesel(table_name = audit_events)]
+        /// This struct is used when returning the entries of the audit log from the database.
+        pub struct AuditEvent {
+            /// The id of the event
+            pub id: i32,
+            /// The UUID of the account the event is about
+            pub account_id: Option<i32>,
+            /// The username the client has entered
+            pub username: Option<String>,
+            /// The IP address of the client
+            pub ip_address: Option<String>,
+            /// The `User-Agent` header sent by the client
+            pub user_agent: Option<String>,
+            /// The kind of the event
+            pub event_type: AuditEventType,
+            /// Whether the action succeeded
+            pub outcome: AuditOutcome,
+            /// Additional information about the event
+            pub details: Option<String>,
+            /// The timestamp taken when the event was recorded
+            pub created_at: chrono::NaiveDateTime,
+        }
+
+        #[derive(Deserialize, Serialize, Clone, Debug, Default)]
+        /// This struct is used when an admin filters the audit log, the page is requested with a separate ```PageRequest```.
+        pub struct AuditEventQuery {
+            /// Only return the events of this account (This field is optional)
+            pub account_id: Option<i32>,
+            /// Only return the events of this kind (This field is optional)
+            pub event_type: Option<AuditEventType>,
+        }
+
+        #[derive(Queryable, Serialize, Deserialize, Clone, Debug, ToSchema)]
+        /// This struct is used when returning the accounts the logged in account has blocked.
+        pub struct BlockedAccount {
+            /// The UUID of the blocked account
+            pub id: i32,
+            /// The username of the blocked account
+            pub username: String,
+            /// The timestamp taken when the account got blocked
+            pub blocked_at: chrono::NaiveDateTime,
+        }
+    }
+}
+
+/// This function takes a password argument which it hashes with ```Argon2``` via the default hasher settings.
+pub fn hash_password(password: &str) -> anyhow::Result<String> {
+    //Create argon2 hasher instance
+    let argon2 = argon2::Argon2::default();
+
+    //Create salt
+    let salt = SaltString::generate(&mut OsRng);
+
+    let start = Instant::now();
+
+    let password_hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|err| anyhow::Error::msg(err.to_string()))?
+        .to_string();
+
+    metrics::histogram!("password_hash_duration_seconds", "operation" => "hash")
+        .record(start.elapsed());
+
+    Ok(password_hash)
+}
+
+/// This function establishes the ```ServerState``` instance
+pub fn establish_server_state() -> anyhow::Result<ServerState> {
+    let database_url = include_str!("..\\..\\.env");
+
+    let connection_manager = ConnectionManager::new(database_url);
+
+    let pool = r2d2::Builder::new().build(connection_manager)?;
+
+    let (notification_sender, _) = broadcast::channel(256);
+
+    Ok(ServerState {
+        pgconnection: pool,
+        notification_sender,
+        shutdown: CancellationToken::new(),
+        response_cache: ResponseCache::new(),
+    })
+}
+
+/// This mod contains `unsafe` function which **will** reveal sensitive information.
+/// These functions should **ONLY** be used in the backend where data security is verified and guaranteed.
+/// The `safe_functions` and `unsafe_functions` mod contains functions which make queries to the database.
+pub mod unsafe_functions {
+    use crate::*;
+
+    /// This function looks up the full account information and returns an ```unsafe_types::AccountLookup``` instance.
+    /// Please note that this function should **NEVER** be used to return data to anywhere other then backend.
+    pub fn __lookup_account_from_id_unsafe(
+        id: i32,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<unsafe_types::AccountLookup> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_only()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    let matched_account: Option<unsafe_types::AccountLookup> =
+                        accounts::dsl::accounts
+                            .filter(accounts::dsl::id.eq(id))
+                            .select(unsafe_types::AccountLookup::as_select())
+                            .first::<unsafe_types::AccountLookup>(conn)
+                            .ok();
+
+                    matched_account.ok_or_else(|| anyhow::Error::msg("Profile not found"))
+                })
+            })
+            .map_err(anyhow::Error::from)
+    }
+}
+
+/// This mod of functions contain `safe` functions which can not reveal sensitive information.
+/// When creating a function in this mod please verify the intergrity.
+/// The `safe_functions` and `unsafe_functions` mod contains functions which make queries to the database.
+pub mod safe_functions {
+    use crate::*;
+    /// This function looks up the public information of an account based on their UUID.
+    /// This function will return an error if the user doesnt exist.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn lookup_account_from_id(id: i32, pgconnection: PgPool) -> anyhow::Result<AccountLookup> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_only()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    let matched_account: Option<AccountLookup> = accounts::dsl::accounts
+                        .filter(accounts::dsl::id.eq(id))
+                        .select(AccountLookup::as_select())
+                        .first(conn)
+                        .ok();
+
+                    matched_account.ok_or_else(|| anyhow::Error::msg("Profile not found"))
+                })
+            })
+            .map_err(anyhow::Error::from)
+    }
+
+    /// This function looks up the public information of an account based on their UUID, the same way ```lookup_account_from_id``` does.
+    /// This function will also return an error if the account is currently hidden by moderation, so it should be used when showing an account to other users.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn lookup_visible_account_from_id(
+        id: i32,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<AccountLookup> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_only()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    let matched_account: Option<AccountLookup> = accounts::dsl::accounts
+                        .filter(accounts::dsl::id.eq(id))
+                        .filter(
+                            accounts::dsl::hidden_at
+                                .is_null()
+                                .or(accounts::dsl::hidden_until.le(diesel::dsl::now)),
+                        )
+                        .select(AccountLookup::as_select())
+                        .first(conn)
+                        .ok();
+
+                    matched_account.ok_or_else(|| anyhow::Error::msg("Profile not found"))
+                })
+            })
+    }
+
+    /// This function returns whether the account is hidden until a moderator restores it (e.g. it has been banned).
+    /// Accounts hidden automatically by reports are hidden until a set time, so they are not suspended and can still log in.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn is_account_suspended(id: i32, pgconnection: PgPool) -> anyhow::Result<bool> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_only()
+            .run(move |conn| {
+                diesel::select(diesel::dsl::exists(
+                    accounts::dsl::accounts
+                        .filter(accounts::dsl::id.eq(id))
+                        .filter(accounts::dsl::hidden_at.is_not_null())
+                        .filter(accounts::dsl::hidden_until.is_null()),
+                ))
+                .get_result(conn)
+                .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function is going to write data to the database and return an ```anyhow::Result<usize>```
+    /// If the query was unsuccessful or didnt find the user it will return ```Ok(usize)```, with the inner value being the nuber of rows inserted.
+    /// If the query was successful and found the user the client requested it will return an ```Error(_)```
+    #[instrument(skip(pgconnection, client_headers), err(level = "warn"))]
+    pub fn handle_account_register_request(
+        request: Account,
+        pgconnection: PgPool,
+        client_headers: HeaderMap,
+    ) -> anyhow::Result<usize> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_write()
+            .run(move |conn| {
+                if let Ok(Some(_)) = accounts::dsl::accounts
+                    .filter(username.eq(&request.username))
+                    .select(Account::as_select())
+                    .first::<Account>(conn)
+                    .optional()
+                {
+                    bail!("User already exists.")
+                } else {
+                    conn.transaction(|conn| {
+                        insert_into(accounts::table)
+                            .values(&request.into_storable())
+                            .execute(conn)
+                    })
+                    .map_err(anyhow::Error::from)
+                }
+            })
+    }
+
+    /// This function is going to read data out of the database and return an ```anyhow::Result<Option<Account>>```
+    /// If the query was unsuccessful or didnt find the user it will return an error.
+    /// If the query was successful and found the user the client requested it will return an ```Account```
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn handle_account_login_request(
+        request: Account,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<unsafe_types::AccountLookup> {
+        let argon2 = Argon2::default();
+
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_only()
+            .run(|conn| {
+                conn.transaction(|conn| {
+                    let matched_account: Option<unsafe_types::AccountLookup> =
+                        accounts::dsl::accounts
+                            //Check for username match
+                            .filter(username.eq(request.username))
+                            //Banned accounts can not log in
+                            .filter(accounts::dsl::banned_at.is_null())
+                            .select(unsafe_types::AccountLookup::as_select())
+                            //Check for password match
+                            .load(conn)?
+                            .into_iter()
+                            .find(|account_lookup| {
+                                let start = Instant::now();
+
+                                let is_matching = argon2
+                                    .verify_password(
+                                        request.passw.as_bytes(),
+                                        &PasswordHash::new(&account_lookup.passw).unwrap(),
+                                    )
+                                    .is_ok();
+
+                                metrics::histogram!(
+                                    "password_hash_duration_seconds",
+                                    "operation" => "verify"
+                                )
+                                .record(start.elapsed());
+
+                                is_matching
+                            });
+
+                    matched_account.ok_or_else(|| anyhow::Error::msg("Profile not found"))
+                })
+            })
+            .map_err(anyhow::Error::from)
+    }
+
+    /// This function takes an ```&AuthorizedUser``` instance which it writes to the database, so that it can be accessed later to authenticate the user
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn record_authenticated_account(
+        authorized_user: &AuthorizedUser,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<usize> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_write()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    insert_into(authorized_users::table)
+                        .values(authorized_user)
+                        .execute(conn)
+                })
+                .map_err(anyhow::Error::from)
+            })
+            .map_err(anyhow::Error::from)
+    }
+
+    /// This function takes an ```&AuthorizedUser``` instance which it check for in the database, so it authenticate the user
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn check_authenticated_account(
+        pgconnection: PgPool,
+        authorized_user: &AuthorizedUser,
+    ) -> Result<Option<AuthorizedUser>, StatusCode> {
+        pgconnection
+            .get()
+            .map_err(|_| StatusCode::REQUEST_TIMEOUT)?
+            .build_transaction()
+            .read_only()
+            .run(|conn| {
+                conn.transaction(|conn| {
+                    let matched_authorized_account = authorized_users::dsl::authorized_users
+                        .filter(session_id.eq(authorized_user.session_id.clone()))
+                        .select(AuthorizedUser::as_select())
+                        .load(conn)?
+                        .into_iter()
+                        .find(|auth_user| {
                             auth_user.client_signature == authorized_user.client_signature
                                 && auth_user.account_id == auth_user.account_id && auth_user.client_signature == authorized_user.client_signature
                         });
 
-                    Ok(matched_authorized_account)
+                    Ok(matched_authorized_account)
+                })
+            })
+            .map_err(|_: Error| StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// This function writes a new ```SavedSearch``` to the database for the account specified in the ```account_id``` argument.
+    /// It returns the stored ```SavedSearch``` with the fields `PostgreSQL` filled out.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn create_saved_search(
+        account_id: i32,
+        request: SavedSearchRequest,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<SavedSearch> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_write()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    insert_into(saved_searches::table)
+                        .values(&NewSavedSearch::from_request(account_id, request))
+                        .returning(SavedSearch::as_returning())
+                        .get_result(conn)
+                })
+                .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function looks up every ```SavedSearch``` owned by the account specified in the ```account_id``` argument.
+    /// The searches are returned in the order they were saved in.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn list_saved_searches(
+        account_id: i32,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<Vec<SavedSearch>> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_only()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    saved_searches::dsl::saved_searches
+                        .filter(saved_searches::dsl::account_id.eq(account_id))
+                        .order(saved_searches::dsl::id.asc())
+                        .select(SavedSearch::as_select())
+                        .load(conn)
+                })
+                .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function overwrites the ```SavedSearch``` specified in the ```id``` argument with the contents of the request.
+    /// This function will return an error if the search doesnt exist or if it is not owned by the account specified in the ```account_id``` argument.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn update_saved_search(
+        account_id: i32,
+        id: i32,
+        request: SavedSearchRequest,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<SavedSearch> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_write()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    diesel::update(
+                        saved_searches::dsl::saved_searches
+                            .filter(saved_searches::dsl::id.eq(id))
+                            .filter(saved_searches::dsl::account_id.eq(account_id)),
+                    )
+                    .set(&NewSavedSearch::from_request(account_id, request))
+                    .returning(SavedSearch::as_returning())
+                    .get_result(conn)
+                    .optional()
+                })
+                .map_err(anyhow::Error::from)?
+                .ok_or_else(|| anyhow::Error::msg("Saved search not found"))
+            })
+    }
+
+    /// This function deletes the ```SavedSearch``` specified in the ```id``` argument.
+    /// This function will return an error if the search doesnt exist or if it is not owned by the account specified in the ```account_id``` argument.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn delete_saved_search(
+        account_id: i32,
+        id: i32,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<usize> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_write()
+            .run(move |conn| {
+                let deleted_rows = conn
+                    .transaction(|conn| {
+                        diesel::delete(
+                            saved_searches::dsl::saved_searches
+                                .filter(saved_searches::dsl::id.eq(id))
+                                .filter(saved_searches::dsl::account_id.eq(account_id)),
+                        )
+                        .execute(conn)
+                    })
+                    .map_err(anyhow::Error::from)?;
+
+                if deleted_rows == 0 {
+                    bail!("Saved search not found")
+                }
+
+                Ok(deleted_rows)
+            })
+    }
+
+    /// This function writes a ```NewNotification``` to the database and returns the stored ```Notification```.
+    /// Please note that this does not push the notification to the account's live connections, use ```send_notification``` for that.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn insert_notification(
+        notification: &NewNotification,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<Notification> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_write()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    insert_into(notifications::table)
+                        .values(notification)
+                        .returning(Notification::as_returning())
+                        .get_result(conn)
+                })
+                .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function looks up a page of the notifications received by the account specified in the ```account_id``` argument.
+    /// The notifications are ordered from the newest to the oldest.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn list_notifications(
+        account_id: i32,
+        page: &PageRequest,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<Vec<Notification>> {
+        let (limit, offset) = page.limit_offset();
+
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_only()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    notifications::dsl::notifications
+                        .filter(notifications::dsl::account_id.eq(account_id))
+                        .order(notifications::dsl::id.desc())
+                        .limit(limit)
+                        .offset(offset)
+                        .select(Notification::as_select())
+                        .load(conn)
+                })
+                .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function counts the unread notifications of the account specified in the ```account_id``` argument.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn count_unread_notifications(account_id: i32, pgconnection: PgPool) -> anyhow::Result<i64> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_only()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    notifications::dsl::notifications
+                        .filter(notifications::dsl::account_id.eq(account_id))
+                        .filter(notifications::dsl::read_at.is_null())
+                        .count()
+                        .get_result(conn)
+                })
+                .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function marks the notifications specified in the ```ids``` argument as read.
+    /// If ```ids``` is ```None``` every unread notification of the account gets marked as read.
+    /// Notifications which are not owned by the account specified in the ```account_id``` argument are left untouched.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn mark_notifications_read(
+        account_id: i32,
+        ids: Option<Vec<i32>>,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<usize> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_write()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    let unread_notifications = notifications::dsl::notifications
+                        .filter(notifications::dsl::account_id.eq(account_id))
+                        .filter(notifications::dsl::read_at.is_null())
+                        .into_boxed();
+
+                    let unread_notifications = match ids {
+                        Some(ids) => {
+                            unread_notifications.filter(notifications::dsl::id.eq_any(ids))
+                        }
+                        None => unread_notifications,
+                    };
+
+                    diesel::update(notifications::table)
+                        .filter(
+                            notifications::dsl::id
+                                .eq_any(unread_notifications.select(notifications::dsl::id)),
+                        )
+                        .set(notifications::dsl::read_at.eq(diesel::dsl::now))
+                        .execute(conn)
+                })
+                .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function looks up every ```Category```, ordered by their name.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn list_categories(pgconnection: PgPool) -> anyhow::Result<Vec<Category>> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_only()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    categories::dsl::categories
+                        .order(categories::dsl::name.asc())
+                        .select(Category::as_select())
+                        .load(conn)
+                })
+                .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function looks up the attribute schema of the category specified in the ```category_id``` argument.
+    /// The returned list is empty if the category has no attributes or does not exist.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn list_category_attributes(
+        category_id: i32,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<Vec<CategoryAttribute>> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_only()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    category_attributes::dsl::category_attributes
+                        .filter(category_attributes::dsl::category_id.eq(category_id))
+                        .order(category_attributes::dsl::id.asc())
+                        .select(CategoryAttribute::as_select())
+                        .load(conn)
+                })
+                .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function creates the category specified in the ```category``` argument.
+    /// This function will return an error if a category with the same name already exists or if the parent category doesnt exist.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn create_category(category: NewCategory, pgconnection: PgPool) -> anyhow::Result<Category> {
+        if category.name.trim().is_empty() {
+            bail!("The name of a category must not be empty.")
+        }
+
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_write()
+            .run(move |conn| {
+                insert_into(categories::table)
+                    .values(&category)
+                    .returning(Category::as_returning())
+                    .get_result(conn)
+                    .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function deletes the category specified in the ```category_id``` argument.
+    /// Please note that the subcategories and the attribute schema of the category get deleted with it.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn delete_category(category_id: i32, pgconnection: PgPool) -> anyhow::Result<usize> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_write()
+            .run(move |conn| {
+                diesel::delete(categories::dsl::categories.filter(categories::dsl::id.eq(category_id)))
+                    .execute(conn)
+                    .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function adds the attribute specified in the ```attribute``` argument to the schema of its category.
+    /// This function will return an error if the attribute is invalid, if the category doesnt exist or if it already has an attribute with the same key.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn create_category_attribute(
+        attribute: NewCategoryAttribute,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<CategoryAttribute> {
+        attribute.validate()?;
+
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_write()
+            .run(move |conn| {
+                insert_into(category_attributes::table)
+                    .values(&attribute)
+                    .returning(CategoryAttribute::as_returning())
+                    .get_result(conn)
+                    .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function deletes the category attribute specified in the ```attribute_id``` argument.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn delete_category_attribute(
+        attribute_id: i32,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<usize> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_write()
+            .run(move |conn| {
+                diesel::delete(
+                    category_attributes::dsl::category_attributes
+                        .filter(category_attributes::dsl::id.eq(attribute_id)),
+                )
+                .execute(conn)
+                .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function looks up the category names most similar to the ```query``` argument, using `pg_trgm` trigram similarity.
+    /// Names containing the query are always included, so that suggestions show up while the user is still typing, misspelled names are included if they are similar enough.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn lookup_search_suggestions(
+        query: String,
+        limit: i64,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<Vec<String>> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_only()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    diesel::sql_query(
+                        "SELECT name AS suggestion FROM categories \
+                         WHERE strpos(lower(name), lower($1)) > 0 OR name % $1 \
+                         ORDER BY similarity(name, $1) DESC, name ASC \
+                         LIMIT $2",
+                    )
+                    .bind::<diesel::sql_types::Text, _>(query)
+                    .bind::<diesel::sql_types::BigInt, _>(limit)
+                    .load::<Suggestion>(conn)
+                })
+                .map_err(anyhow::Error::from)
+            })
+            .map(|suggestions| {
+                suggestions
+                    .into_iter()
+                    .map(|suggestion| suggestion.suggestion)
+                    .collect()
+            })
+    }
+
+    /// This function adds the account specified in the ```blocked_id``` argument to the block list of the account specified in the ```blocker_id``` argument.
+    /// Blocking an account which is already blocked is not an error, this function will return an error if an account tries to block itself or the blocked account doesnt exist.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn block_account(
+        blocker_id: i32,
+        blocked_id: i32,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<usize> {
+        if blocker_id == blocked_id {
+            bail!("Accounts can not block themselves.")
+        }
+
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_write()
+            .run(move |conn| {
+                let blocked_account_exists: bool = diesel::select(diesel::dsl::exists(
+                    accounts::dsl::accounts.filter(accounts::dsl::id.eq(blocked_id)),
+                ))
+                .get_result(conn)?;
+
+                if !blocked_account_exists {
+                    bail!("Profile not found")
+                }
+
+                insert_into(account_blocks::table)
+                    .values((
+                        account_blocks::dsl::blocker_id.eq(blocker_id),
+                        account_blocks::dsl::blocked_id.eq(blocked_id),
+                    ))
+                    .on_conflict_do_nothing()
+                    .execute(conn)
+                    .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function removes the account specified in the ```blocked_id``` argument from the block list of the account specified in the ```blocker_id``` argument.
+    /// It returns the number of removed blocks, which is 0 if the account wasnt blocked.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn unblock_account(
+        blocker_id: i32,
+        blocked_id: i32,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<usize> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_write()
+            .run(move |conn| {
+                diesel::delete(
+                    account_blocks::dsl::account_blocks
+                        .filter(account_blocks::dsl::blocker_id.eq(blocker_id))
+                        .filter(account_blocks::dsl::blocked_id.eq(blocked_id)),
+                )
+                .execute(conn)
+                .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function looks up the block list of the account specified in the ```blocker_id``` argument, the most recently blocked account first.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn list_blocked_accounts(
+        blocker_id: i32,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<Vec<BlockedAccount>> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_only()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    account_blocks::dsl::account_blocks
+                        .inner_join(
+                            accounts::table
+                                .on(accounts::dsl::id.eq(account_blocks::dsl::blocked_id)),
+                        )
+                        .filter(account_blocks::dsl::blocker_id.eq(blocker_id))
+                        .order(account_blocks::dsl::created_at.desc())
+                        .select((
+                            accounts::dsl::id,
+                            accounts::dsl::username,
+                            account_blocks::dsl::created_at,
+                        ))
+                        .load::<BlockedAccount>(conn)
+                })
+                .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function checks whether the account specified in the ```blocker_id``` argument has blocked the account specified in the ```blocked_id``` argument.
+    /// Every code path where an account reaches out to another one (e.g. sending a message) has to check this with the acting account as ```blocked_id```, and refuse the action if it returns ```true```.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn is_account_blocked(
+        blocker_id: i32,
+        blocked_id: i32,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<bool> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_only()
+            .run(move |conn| {
+                diesel::select(diesel::dsl::exists(
+                    account_blocks::dsl::account_blocks
+                        .filter(account_blocks::dsl::blocker_id.eq(blocker_id))
+                        .filter(account_blocks::dsl::blocked_id.eq(blocked_id)),
+                ))
+                .get_result(conn)
+                .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function writes a new ```Report``` made by the account specified in the ```reporter_id``` argument to the database.
+    /// If the target has pending reports from at least ```REPORT_HIDE_THRESHOLD``` distinct accounts, it gets hidden for ```AUTO_HIDE_HOURS``` hours until a moderator reviews it.
+    /// This function will return an error if the target doesnt exist, if an account reports itself, if the target account has blocked the reporter or if the account already has a pending report against the target.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn create_report(
+        reporter_id: i32,
+        request: ReportRequest,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<Report> {
+        match request.target_kind {
+            TargetKind::Account => {
+                //Blocked accounts can not get the blocker hidden by reporting it, they get the same error as if the blocker didnt exist
+                if is_account_blocked(request.target_id, reporter_id, pgconnection.clone())? {
+                    bail!("Profile not found")
+                }
+            }
+        }
+
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_write()
+            .run(move |conn| {
+                match request.target_kind {
+                    TargetKind::Account => {
+                        if request.target_id == reporter_id {
+                            bail!("Accounts can not report themselves.")
+                        }
+
+                        //The target stays locked until the transaction ends, so concurrent reports are counted one after the other, and they can not all miss the threshold
+                        let locked_target: Option<i32> = accounts::dsl::accounts
+                            .filter(accounts::dsl::id.eq(request.target_id))
+                            .select(accounts::dsl::id)
+                            .for_update()
+                            .first(conn)
+                            .optional()?;
+
+                        if locked_target.is_none() {
+                            bail!("Profile not found")
+                        }
+                    }
+                }
+
+                let report = insert_into(reports::table)
+                    .values(&NewReport {
+                        reporter_id,
+                        target_kind: request.target_kind,
+                        target_id: request.target_id,
+                        reason: request.reason,
+                        details: request.details,
+                    })
+                    .returning(Report::as_returning())
+                    .get_result(conn)?;
+
+                //An account can only have one pending report against a target, so this is the number of distinct reporters
+                let distinct_reporters: i64 = reports::dsl::reports
+                    .filter(reports::dsl::target_kind.eq(request.target_kind))
+                    .filter(reports::dsl::target_id.eq(request.target_id))
+                    .filter(reports::dsl::status.eq(ReportStatus::Pending))
+                    .count()
+                    .get_result(conn)?;
+
+                if distinct_reporters >= REPORT_HIDE_THRESHOLD {
+                    let hidden_rows = match request.target_kind {
+                        TargetKind::Account => diesel::update(
+                            accounts::dsl::accounts
+                                .filter(accounts::dsl::id.eq(request.target_id))
+                                //Only hide the account if it is not hidden already, so that a hide by a moderator doesnt get a time limit
+                                .filter(
+                                    accounts::dsl::hidden_at
+                                        .is_null()
+                                        .or(accounts::dsl::hidden_until.le(diesel::dsl::now)),
+                                ),
+                        )
+                        .set((
+                            accounts::dsl::hidden_at.eq(diesel::dsl::now),
+                            accounts::dsl::hidden_until
+                                .eq((diesel::dsl::now + AUTO_HIDE_HOURS.hours()).nullable()),
+                        ))
+                        .execute(conn)?,
+                    };
+
+                    if hidden_rows != 0 {
+                        insert_into(moderation_log::table)
+                            .values(&NewModerationLogEntry {
+                                moderator_id: None,
+                                action: ModerationAction::AutoHide,
+                                target_kind: request.target_kind,
+                                target_id: request.target_id,
+                                note: Some(format!("Reported by {distinct_reporters} accounts.")),
+                            })
+                            .execute(conn)?;
+                    }
+                }
+
+                Ok(report)
+            })
+    }
+
+    /// This function looks up a page of the pending reports in the order they were made in, this is the moderation queue.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn list_pending_reports(
+        page: &PageRequest,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<Vec<Report>> {
+        let (limit, offset) = page.limit_offset();
+
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_only()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    reports::dsl::reports
+                        .filter(reports::dsl::status.eq(ReportStatus::Pending))
+                        .order(reports::dsl::id.asc())
+                        .limit(limit)
+                        .offset(offset)
+                        .select(Report::as_select())
+                        .load(conn)
+                })
+                .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function takes the action specified in the request against the target, on behalf of the moderator specified in the ```moderator_id``` argument.
+    /// Every pending report against the target gets closed, and the action gets written to the moderation audit log, which is returned.
+    /// This function will return an error if the target doesnt exist or if the action is ```ModerationAction::AutoHide```, as that is only taken by the backend itself.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn moderate_target(
+        moderator_id: i32,
+        request: ModerationRequest,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<ModerationLogEntry> {
+        if request.action == ModerationAction::AutoHide {
+            bail!("Targets can only be hidden automatically by the backend.")
+        }
+
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_write()
+            .run(move |conn| {
+                match request.target_kind {
+                    TargetKind::Account => {
+                        let target_account =
+                            accounts::dsl::accounts.filter(accounts::dsl::id.eq(request.target_id));
+
+                        let updated_rows = match request.action {
+                            ModerationAction::Hide => diesel::update(target_account)
+                                .set((
+                                    accounts::dsl::hidden_at.eq(diesel::dsl::now),
+                                    accounts::dsl::hidden_until.eq(None::<chrono::NaiveDateTime>),
+                                ))
+                                .execute(conn)?,
+                            ModerationAction::Restore => diesel::update(target_account)
+                                .set((
+                                    accounts::dsl::hidden_at.eq(None::<chrono::NaiveDateTime>),
+                                    accounts::dsl::hidden_until.eq(None::<chrono::NaiveDateTime>),
+                                    accounts::dsl::banned_at.eq(None::<chrono::NaiveDateTime>),
+                                ))
+                                .execute(conn)?,
+                            ModerationAction::Ban => {
+                                let updated_rows = diesel::update(target_account)
+                                    .set((
+                                        accounts::dsl::banned_at.eq(diesel::dsl::now),
+                                        accounts::dsl::hidden_at.eq(diesel::dsl::now),
+                                        accounts::dsl::hidden_until
+                                            .eq(None::<chrono::NaiveDateTime>),
+                                    ))
+                                    .execute(conn)?;
+
+                                //End every session of the banned account
+                                diesel::delete(authorized_users::dsl::authorized_users.filter(
+                                    authorized_users::dsl::account_id.eq(request.target_id),
+                                ))
+                                .execute(conn)?;
+
+                                updated_rows
+                            }
+                            ModerationAction::RevokeSessions => {
+                                diesel::delete(authorized_users::dsl::authorized_users.filter(
+                                    authorized_users::dsl::account_id.eq(request.target_id),
+                                ))
+                                .execute(conn)?;
+
+                                diesel::select(diesel::dsl::exists(target_account))
+                                    .get_result::<bool>(conn)? as usize
+                            }
+                            ModerationAction::Dismiss | ModerationAction::AutoHide => {
+                                diesel::select(diesel::dsl::exists(target_account))
+                                    .get_result::<bool>(conn)? as usize
+                            }
+                        };
+
+                        if updated_rows == 0 {
+                            bail!("Profile not found")
+                        }
+                    }
+                }
+
+                // Revoking the sessions of an account doesnt settle the reports against it
+                let report_status = match request.action {
+                    ModerationAction::Hide | ModerationAction::Ban => Some(ReportStatus::Resolved),
+                    ModerationAction::Restore
+                    | ModerationAction::Dismiss
+                    | ModerationAction::AutoHide => Some(ReportStatus::Dismissed),
+                    ModerationAction::RevokeSessions => None,
+                };
+
+                if let Some(report_status) = report_status {
+                    diesel::update(
+                        reports::dsl::reports
+                            .filter(reports::dsl::target_kind.eq(request.target_kind))
+                            .filter(reports::dsl::target_id.eq(request.target_id))
+                            .filter(reports::dsl::status.eq(ReportStatus::Pending)),
+                    )
+                    .set((
+                        reports::dsl::status.eq(report_status),
+                        reports::dsl::resolved_by.eq(moderator_id),
+                        reports::dsl::resolved_at.eq(diesel::dsl::now),
+                    ))
+                    .execute(conn)?;
+                }
+
+                insert_into(moderation_log::table)
+                    .values(&NewModerationLogEntry {
+                        moderator_id: Some(moderator_id),
+                        action: request.action,
+                        target_kind: request.target_kind,
+                        target_id: request.target_id,
+                        note: request.note,
+                    })
+                    .returning(ModerationLogEntry::as_returning())
+                    .get_result(conn)
+                    .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function looks up a page of the moderation audit log, the newest entry first.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn list_moderation_log(
+        page: &PageRequest,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<Vec<ModerationLogEntry>> {
+        let (limit, offset) = page.limit_offset();
+
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_only()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    moderation_log::dsl::moderation_log
+                        .order(moderation_log::dsl::id.desc())
+                        .limit(limit)
+                        .offset(offset)
+                        .select(ModerationLogEntry::as_select())
+                        .load(conn)
+                })
+                .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function looks up every permission granted to the account specified in the ```account_id``` argument through its roles.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn lookup_account_permissions(
+        account_id: i32,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<Vec<Permission>> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_only()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    role_permissions::dsl::role_permissions
+                        .inner_join(
+                            account_roles::table
+                                .on(account_roles::dsl::role_id.eq(role_permissions::dsl::role_id)),
+                        )
+                        .filter(account_roles::dsl::account_id.eq(account_id))
+                        .select(role_permissions::dsl::permission)
+                        .distinct()
+                        .load::<Permission>(conn)
+                })
+                .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function grants the role specified in the ```role``` argument to the account with the username specified in the ```account_username``` argument.
+    /// Granting a role the account already has is not an error, this function will return an error if either the account or the role doesnt exist.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn grant_role(
+        account_username: &str,
+        role: &str,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<usize> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_write()
+            .run(move |conn| {
+                let (account_id, role_id) = lookup_account_and_role_ids(account_username, role, conn)?;
+
+                insert_into(account_roles::table)
+                    .values((
+                        account_roles::dsl::account_id.eq(account_id),
+                        account_roles::dsl::role_id.eq(role_id),
+                    ))
+                    .on_conflict_do_nothing()
+                    .execute(conn)
+                    .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function revokes the role specified in the ```role``` argument from the account with the username specified in the ```account_username``` argument.
+    /// Revoking a role the account doesnt have is not an error, this function will return an error if either the account or the role doesnt exist.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn revoke_role(
+        account_username: &str,
+        role: &str,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<usize> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_write()
+            .run(move |conn| {
+                let (account_id, role_id) = lookup_account_and_role_ids(account_username, role, conn)?;
+
+                diesel::delete(
+                    account_roles::dsl::account_roles
+                        .filter(account_roles::dsl::account_id.eq(account_id))
+                        .filter(account_roles::dsl::role_id.eq(role_id)),
+                )
+                .execute(conn)
+                .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function looks up the UUID of the account with the username specified in the ```account_username``` argument, and the id of the role specified in the ```role``` argument.
+    fn lookup_account_and_role_ids(
+        account_username: &str,
+        role: &str,
+        conn: &mut PgConnection,
+    ) -> anyhow::Result<(i32, i32)> {
+        let account_id = accounts::dsl::accounts
+            .filter(username.eq(account_username))
+            .select(accounts::dsl::id)
+            .first::<i32>(conn)
+            .optional()?
+            .ok_or_else(|| anyhow::Error::msg("Profile not found"))?;
+
+        let role_id = roles::dsl::roles
+            .filter(roles::dsl::name.eq(role))
+            .select(roles::dsl::id)
+            .first::<i32>(conn)
+            .optional()?
+            .ok_or_else(|| anyhow::Error::msg("Role not found"))?;
+
+        Ok((account_id, role_id))
+    }
+
+    /// This function looks up a page of the accounts whose username contains the ```query``` argument, ignoring case.
+    /// Every account is returned if the query is empty, the accounts are ordered by their UUID.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn search_accounts(
+        query: String,
+        page: &PageRequest,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<Vec<AdminAccountLookup>> {
+        let (limit, offset) = page.limit_offset();
+
+        // Escape the wildcards so that they are matched literally
+        let pattern = format!(
+            "%{}%",
+            query
+                .trim()
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_only()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    accounts::dsl::accounts
+                        .filter(username.ilike(pattern))
+                        .order(accounts::dsl::id.asc())
+                        .limit(limit)
+                        .offset(offset)
+                        .select(AdminAccountLookup::as_select())
+                        .load(conn)
+                })
+                .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function counts the rows shown on the statistics page of the admin panel.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn lookup_site_statistics(pgconnection: PgPool) -> anyhow::Result<SiteStatistics> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_only()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    Ok(SiteStatistics {
+                        account_count: accounts::dsl::accounts.count().get_result(conn)?,
+                        hidden_account_count: accounts::dsl::accounts
+                            .filter(accounts::dsl::hidden_at.is_not_null())
+                            .count()
+                            .get_result(conn)?,
+                        suspended_account_count: accounts::dsl::accounts
+                            .filter(accounts::dsl::banned_at.is_not_null())
+                            .count()
+                            .get_result(conn)?,
+                        session_count: authorized_users::dsl::authorized_users
+                            .count()
+                            .get_result(conn)?,
+                        pending_report_count: reports::dsl::reports
+                            .filter(reports::dsl::status.eq(ReportStatus::Pending))
+                            .count()
+                            .get_result(conn)?,
+                        category_count: categories::dsl::categories.count().get_result(conn)?,
+                        saved_search_count: saved_searches::dsl::saved_searches
+                            .count()
+                            .get_result(conn)?,
+                    })
+                })
+                .map_err(|err: diesel::result::Error| anyhow::Error::from(err))
+            })
+    }
+
+    /// This function appends the event passed in to the audit log.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn record_audit_event(event: NewAuditEvent, pgconnection: PgPool) -> anyhow::Result<usize> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_write()
+            .run(move |conn| {
+                insert_into(audit_events::table)
+                    .values(&event)
+                    .execute(conn)
+                    .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function looks up a page of the audit log matching the filters in the ```query``` argument, the newest event first.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn list_audit_events(
+        query: &AuditEventQuery,
+        page: &PageRequest,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<Vec<AuditEvent>> {
+        let (limit, offset) = page.limit_offset();
+        let query = query.clone();
+
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_only()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    let mut events = audit_events::dsl::audit_events.into_boxed();
+
+                    if let Some(account_id) = query.account_id {
+                        events = events.filter(audit_events::dsl::account_id.eq(account_id));
+                    }
+
+                    if let Some(event_type) = query.event_type {
+                        events = events.filter(audit_events::dsl::event_type.eq(event_type));
+                    }
+
+                    events
+                        .order(audit_events::dsl::id.desc())
+                        .limit(limit)
+                        .offset(offset)
+                        .select(AuditEvent::as_select())
+                        .load(conn)
+                })
+                .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function deletes the events older than the number of days specified in the ```retention_days``` argument from the audit log.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn prune_audit_events(retention_days: i32, pgconnection: PgPool) -> anyhow::Result<usize> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_write()
+            .run(move |conn| {
+                diesel::delete(
+                    audit_events::dsl::audit_events
+                        .filter(audit_events::dsl::created_at.lt(diesel::dsl::now - retention_days.days())),
+                )
+                .execute(conn)
+                .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function looks up the exchange rate of every currency prices can be normalized from.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn list_exchange_rates(pgconnection: PgPool) -> anyhow::Result<Vec<ExchangeRateEntry>> {
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_only()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    exchange_rates::dsl::exchange_rates
+                        .order(exchange_rates::dsl::currency.asc())
+                        .select(ExchangeRateEntry::as_select())
+                        .load(conn)
+                })
+                .map_err(anyhow::Error::from)
+            })
+    }
+
+    /// This function sets the exchange rate of the currency specified in the ```currency``` argument, creating the entry if it doesnt exist yet.
+    /// The rate is the number of millionths of a forint one minor unit of the currency is worth, it will return an error if the rate is not positive or the currency is the forint itself.
+    #[instrument(skip(pgconnection), err(level = "warn"))]
+    pub fn set_exchange_rate(
+        currency: CurrencyCode,
+        rate_to_huf: i64,
+        pgconnection: PgPool,
+    ) -> anyhow::Result<ExchangeRateEntry> {
+        if currency == CurrencyCode::Huf {
+            bail!("The forint is the base currency.")
+        }
+
+        if rate_to_huf <= 0 {
+            bail!("Exchange rates must be positive.")
+        }
+
+        pgconnection
+            .get()?
+            .build_transaction()
+            .read_write()
+            .run(move |conn| {
+                conn.transaction(|conn| {
+                    insert_into(exchange_rates::table)
+                        .values((
+                            exchange_rates::dsl::currency.eq(currency),
+                            exchange_rates::dsl::rate_to_huf.eq(rate_to_huf),
+                        ))
+                        .on_conflict(exchange_rates::dsl::currency)
+                        .do_update()
+                        .set((
+                            exchange_rates::dsl::rate_to_huf.eq(rate_to_huf),
+                            exchange_rates::dsl::updated_at.eq(diesel::dsl::now),
+                        ))
+                        .returning(ExchangeRateEntry::as_returning())
+                        .get_result(conn)
                 })
+                .map_err(anyhow::Error::from)
             })
-            .map_err(|_: Error| StatusCode::INTERNAL_SERVER_ERROR)
     }
 }
 
 /// This function will register a new account depending on the request it takes.
 /// It can either return ```StatusCode::CREATED```: When the account has been successfuly registered
 /// Or return ```StatusCode::FOUND```: When the account has been already registered, thus it will not create another one
+#[utoipa::path(
+    post,
+    path = "/api/v1/accounts",
+    tag = "accounts",
+    request_body = Account,
+    responses(
+        (status = CREATED, description = "The account has been registered"),
+        (status = FOUND, description = "The username is already taken"),
+    )
+)]
 pub async fn get_account_register_request(
     State(state): State<ServerState>,
     header: HeaderMap,
+    client: ClientInfo,
     Json(body): Json<Account>,
 ) -> StatusCode {
-    match handle_account_register_request(body, state.pgconnection.clone(), header) {
-        Ok(_) => StatusCode::CREATED,
-        Err(_err) => StatusCode::FOUND,
-    }
+    let requested_username = body.username.clone();
+
+    let (outcome, status_code) =
+        match handle_account_register_request(body, state.pgconnection.clone(), header) {
+            Ok(_) => (AuditOutcome::Success, StatusCode::CREATED),
+            Err(_err) => (AuditOutcome::Failure, StatusCode::FOUND),
+        };
+
+    let _ = record_audit_event(
+        NewAuditEvent {
+            username: Some(requested_username),
+            ..NewAuditEvent::from_client(AuditEventType::Registration, outcome, &client)
+        },
+        state.pgconnection.clone(),
+    );
+
+    status_code
 }
 
 /// This function will create a request to the database whether the account's username is found.
 /// If the password to that account matches it will create an authenticated session_id and set the client's storage
 /// If the account is either not found or an invalid password is entered this function will return ```StatusCode::NOT_FOUND```
+#[utoipa::path(
+    post,
+    path = "/api/v1/sessions",
+    tag = "accounts",
+    request_body = Account,
+    responses(
+        (status = OK, description = "The `session_id` cookie has been set, the body contains the account serialized as a string", body = String),
+        (status = NOT_FOUND, description = "The username or the password is invalid"),
+    )
+)]
 pub async fn get_account_login_request(
     jar: CookieJar,
     State(state): State<ServerState>,
     header: HeaderMap,
+    client: ClientInfo,
     Json(body): Json<Account>,
 ) -> Result<(CookieJar, Json<String>), StatusCode> {
-    let account = handle_account_login_request(body, state.pgconnection.clone())
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+    let requested_username = body.username.clone();
+
+    let login_result = handle_account_login_request(body, state.pgconnection.clone());
+
+    let outcome = match &login_result {
+        Ok(_) => AuditOutcome::Success,
+        Err(_err) => AuditOutcome::Failure,
+    };
+
+    metrics::counter!("logins_total", "outcome" => outcome.as_str()).increment(1);
+
+    let _ = record_audit_event(
+        match &login_result {
+            Ok(account) => NewAuditEvent {
+                account_id: Some(account.id),
+                username: Some(requested_username),
+                ..NewAuditEvent::from_client(AuditEventType::Login, outcome, &client)
+            },
+            Err(_err) => NewAuditEvent {
+                username: Some(requested_username),
+                ..NewAuditEvent::from_client(AuditEventType::Login, outcome, &client)
+            },
+        },
+        state.pgconnection.clone(),
+    );
+
+    let account = login_result.map_err(|_| StatusCode::NOT_FOUND)?;
 
     let authorized_user = AuthorizedUser::from_account(
         &account,
@@ -432,6 +2858,7 @@ pub async fn get_account_login_request(
             Cookie::build(Cookie::new("session_id", authorized_user.to_string()))
                 .permanent()
                 .path("/")
+                .secure(true)
                 .http_only(false)
                 .same_site(axum_extra::extract::cookie::SameSite::Lax)
                 .build(),
@@ -443,41 +2870,930 @@ pub async fn get_account_login_request(
 /// This function will create a request to the database to find the account specified in the ID argument
 /// If the account is found this function  will return a ```Json<safe_types::AccountLookup>```
 /// If the account is not found it wil return ```StatusCode::NOT_FOUND```
+/// This route is deprecated in favor of ```get_v1_account_request```.
 pub async fn get_account_id_account_request(
-    State(state): State<ServerState>,
+    state: State<ServerState>,
+    viewer: Result<AuthenticatedAccount, AuthenticationRejection>,
     Json(id): Json<i32>,
-) -> Result<Json<AccountLookup>, StatusCode> {
-    let account = lookup_account_from_id(id, state.pgconnection.clone())
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+) -> Result<CachedJson, StatusCode> {
+    get_v1_account_request(state, viewer, Path(id)).await
+}
+
+/// This function will create a request to the database to find the account specified in the path.
+/// If the account is found this function will return a ```Json<safe_types::AccountLookup>```, which is cached and can be revalidated with its `ETag`
+/// If the account is not found, is hidden or has blocked the logged in account it will return ```StatusCode::NOT_FOUND```
+#[utoipa::path(
+    get,
+    path = "/api/v1/accounts/{id}",
+    tag = "accounts",
+    security((), ("session_cookie" = [])),
+    params(("id" = i32, Path, description = "The ID of the account")),
+    responses(
+        (status = OK, body = AccountLookup),
+        (status = NOT_FOUND, description = "The account doesnt exist, is hidden or has blocked the logged in account"),
+    )
+)]
+pub async fn get_v1_account_request(
+    State(state): State<ServerState>,
+    viewer: Result<AuthenticatedAccount, AuthenticationRejection>,
+    Path(id): Path<i32>,
+) -> Result<CachedJson, StatusCode> {
+    // The profile is public, so requests without a valid session get it too
+    match viewer {
+        Ok(viewer) => {
+            if is_account_blocked(id, viewer.account.id, state.pgconnection.clone())
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            {
+                return Err(StatusCode::NOT_FOUND);
+            }
+        }
+        Err(AuthenticationRejection::DatabaseError) => {
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Err(_) => (),
+    }
 
-    Ok(Json(account))
+    state
+        .response_cache
+        .account(id, state.pgconnection.clone())
+        .map_err(|_| StatusCode::NOT_FOUND)
 }
 
-/// This function takes a ```Json<AuthorizedUser>``` and validates it with the database, then it looks up the account based on the account's id then returns the ```AccountLookup``` instance from the database.
-/// If the account based on that id is not found it will return ```StatusCode::NOT_FOUND```  
-/// If the ```AuthorizedUser``` instance is invalid it will return ```StatusCode::BAD_REQUEST```  
+/// This function returns the ```AccountLookup``` instance of the logged in account.
+/// The `session_id` cookie is validated by the ```AuthenticatedAccount``` extractor, if it is invalid the cookie gets removed and ```StatusCode::UNAUTHORIZED``` is returned.
+#[utoipa::path(
+    get,
+    path = "/api/v1/me",
+    tag = "me",
+    security(("session_cookie" = [])),
+    responses(
+        (status = OK, body = AccountLookup),
+        (status = UNAUTHORIZED, description = "The `session_id` cookie is missing or invalid"),
+    )
+)]
 pub async fn get_cookie_account_request(
+    authenticated_account: AuthenticatedAccount,
+) -> Json<AccountLookup> {
+    Json(authenticated_account.account)
+}
+
+/// This function will save the search specified in the request for the logged in account.
+/// If the search has been saved it will return the stored ```Json<SavedSearch>```
+/// If the request is invalid (empty query or inverted price range) it will return ```StatusCode::BAD_REQUEST```
+#[utoipa::path(
+    post,
+    path = "/api/v1/me/saved_searches",
+    tag = "me",
+    security(("session_cookie" = [])),
+    request_body = SavedSearchRequest,
+    responses(
+        (status = OK, body = SavedSearch),
+        (status = BAD_REQUEST, description = "The query is empty or the price range is inverted"),
+        (status = UNAUTHORIZED, description = "The `session_id` cookie is missing or invalid"),
+    )
+)]
+pub async fn get_saved_search_create_request(
     State(state): State<ServerState>,
-    jar: CookieJar,
-) -> Result<Json<AccountLookup>, (CookieJar, StatusCode)> {
-    if let Some(session_id_value) = jar.get("session_id") {
-        let authorized_user = serde_json::from_str::<AuthorizedUser>(session_id_value.value()).map_err(|_| (jar.clone().remove("session_id"), StatusCode::BAD_REQUEST))?;
+    authenticated_account: AuthenticatedAccount,
+    Json(body): Json<SavedSearchRequest>,
+) -> Result<Json<SavedSearch>, StatusCode> {
+    if !body.is_valid() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let saved_search = create_saved_search(
+        authenticated_account.account.id,
+        body,
+        state.pgconnection.clone(),
+    )
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(saved_search))
+}
+
+/// This function will return every search the logged in account has saved as a ```Json<Vec<SavedSearch>>```
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/saved_searches",
+    tag = "me",
+    security(("session_cookie" = [])),
+    responses(
+        (status = OK, body = Vec<SavedSearch>),
+        (status = UNAUTHORIZED, description = "The `session_id` cookie is missing or invalid"),
+    )
+)]
+pub async fn get_saved_search_list_request(
+    State(state): State<ServerState>,
+    authenticated_account: AuthenticatedAccount,
+) -> Result<Json<Vec<SavedSearch>>, StatusCode> {
+    let saved_searches = list_saved_searches(authenticated_account.account.id, state.pgconnection.clone())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(saved_searches))
+}
+
+/// This function will overwrite one of the logged in account's saved searches, this is also how the alert frequency of a search can be changed.
+/// If the search is not found or is owned by another account it will return ```StatusCode::NOT_FOUND```
+/// If the request is invalid (empty query or inverted price range) it will return ```StatusCode::BAD_REQUEST```
+/// This route is deprecated in favor of ```get_v1_saved_search_update_request```.
+pub async fn get_saved_search_update_request(
+    state: State<ServerState>,
+    authenticated_account: AuthenticatedAccount,
+    Json((id, body)): Json<(i32, SavedSearchRequest)>,
+) -> Result<Json<SavedSearch>, StatusCode> {
+    get_v1_saved_search_update_request(state, authenticated_account, Path(id), Json(body)).await
+}
+
+/// This function will overwrite the saved search specified in the path, this is also how the alert frequency of a search can be changed.
+/// If the search is not found or is owned by another account it will return ```StatusCode::NOT_FOUND```
+/// If the request is invalid (empty query or inverted price range) it will return ```StatusCode::BAD_REQUEST```
+#[utoipa::path(
+    put,
+    path = "/api/v1/me/saved_searches/{id}",
+    tag = "me",
+    security(("session_cookie" = [])),
+    params(("id" = i32, Path, description = "The ID of the saved search")),
+    request_body = SavedSearchRequest,
+    responses(
+        (status = OK, body = SavedSearch),
+        (status = BAD_REQUEST, description = "The query is empty or the price range is inverted"),
+        (status = UNAUTHORIZED, description = "The `session_id` cookie is missing or invalid"),
+        (status = NOT_FOUND, description = "The search doesnt exist or is owned by another account"),
+    )
+)]
+pub async fn get_v1_saved_search_update_request(
+    State(state): State<ServerState>,
+    authenticated_account: AuthenticatedAccount,
+    Path(id): Path<i32>,
+    Json(body): Json<SavedSearchRequest>,
+) -> Result<Json<SavedSearch>, StatusCode> {
+    if !body.is_valid() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let saved_search = update_saved_search(
+        authenticated_account.account.id,
+        id,
+        body,
+        state.pgconnection.clone(),
+    )
+    .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    Ok(Json(saved_search))
+}
+
+/// This function will delete one of the logged in account's saved searches.
+/// If the search is not found or is owned by another account it will return ```StatusCode::NOT_FOUND```
+/// This route is deprecated in favor of ```get_v1_saved_search_delete_request```.
+pub async fn get_saved_search_delete_request(
+    state: State<ServerState>,
+    authenticated_account: AuthenticatedAccount,
+    Json(id): Json<i32>,
+) -> StatusCode {
+    get_v1_saved_search_delete_request(state, authenticated_account, Path(id)).await
+}
+
+/// This function will delete the saved search specified in the path.
+/// If the search is not found or is owned by another account it will return ```StatusCode::NOT_FOUND```
+#[utoipa::path(
+    delete,
+    path = "/api/v1/me/saved_searches/{id}",
+    tag = "me",
+    security(("session_cookie" = [])),
+    params(("id" = i32, Path, description = "The ID of the saved search")),
+    responses(
+        (status = OK, description = "The search has been deleted"),
+        (status = UNAUTHORIZED, description = "The `session_id` cookie is missing or invalid"),
+        (status = NOT_FOUND, description = "The search doesnt exist or is owned by another account"),
+    )
+)]
+pub async fn get_v1_saved_search_delete_request(
+    State(state): State<ServerState>,
+    authenticated_account: AuthenticatedAccount,
+    Path(id): Path<i32>,
+) -> StatusCode {
+    match delete_saved_search(
+        authenticated_account.account.id,
+        id,
+        state.pgconnection.clone(),
+    ) {
+        Ok(_) => StatusCode::OK,
+        Err(_err) => StatusCode::NOT_FOUND,
+    }
+}
+
+/// This function is the producer API of the notification center, any backend subsystem can use it to notify an account.
+/// It stores the notification in the database and pushes it to every live connection of the receiving account.
+pub fn send_notification(
+    state: &ServerState,
+    notification: NewNotification,
+) -> anyhow::Result<Notification> {
+    let notification = insert_notification(&notification, state.pgconnection.clone())?;
+
+    // Sending only fails if there are no live connections, which is fine as the notification is already stored
+    let _ = state.notification_sender.send(notification.clone());
+
+    Ok(notification)
+}
+
+/// This function will return a page of the logged in account's notifications as a ```Json<NotificationList>```
+/// The page can be specified with the `page` and `per_page` query parameters.
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/notifications",
+    tag = "me",
+    security(("session_cookie" = [])),
+    params(PageRequest),
+    responses(
+        (status = OK, body = NotificationList),
+        (status = UNAUTHORIZED, description = "The `session_id` cookie is missing or invalid"),
+    )
+)]
+pub async fn get_notifications_request(
+    State(state): State<ServerState>,
+    authenticated_account: AuthenticatedAccount,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<NotificationList>, StatusCode> {
+    let notifications =
+        list_notifications(authenticated_account.account.id, &page, state.pgconnection.clone())
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let unread_count =
+        count_unread_notifications(authenticated_account.account.id, state.pgconnection.clone())
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(NotificationList {
+        notifications,
+        unread_count,
+    }))
+}
+
+/// This function will return the number of unread notifications the logged in account has.
+/// This is what the unread badge on the frontend displays.
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/notifications/unread_count",
+    tag = "me",
+    security(("session_cookie" = [])),
+    responses(
+        (status = OK, body = i64),
+        (status = UNAUTHORIZED, description = "The `session_id` cookie is missing or invalid"),
+    )
+)]
+pub async fn get_unread_notification_count_request(
+    State(state): State<ServerState>,
+    authenticated_account: AuthenticatedAccount,
+) -> Result<Json<i64>, StatusCode> {
+    let unread_count =
+        count_unread_notifications(authenticated_account.account.id, state.pgconnection.clone())
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(unread_count))
+}
+
+/// This function will mark the notifications specified in the request as read.
+/// If the request contains ```null``` every notification of the logged in account gets marked as read.
+#[utoipa::path(
+    post,
+    path = "/api/v1/me/notifications/read",
+    tag = "me",
+    security(("session_cookie" = [])),
+    request_body(content = Option<Vec<i32>>, description = "The IDs of the notifications, or ```null``` to mark every notification as read"),
+    responses(
+        (status = OK, description = "The number of notifications marked as read", body = usize),
+        (status = UNAUTHORIZED, description = "The `session_id` cookie is missing or invalid"),
+    )
+)]
+pub async fn get_notifications_read_request(
+    State(state): State<ServerState>,
+    authenticated_account: AuthenticatedAccount,
+    Json(ids): Json<Option<Vec<i32>>>,
+) -> Result<Json<usize>, StatusCode> {
+    let updated_rows =
+        mark_notifications_read(authenticated_account.account.id, ids, state.pgconnection.clone())
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(updated_rows))
+}
+
+/// This function upgrades the connection to a WebSocket which the logged in account's new notifications are pushed through.
+/// If the `session_id` cookie is missing or invalid it will return ```StatusCode::UNAUTHORIZED``` instead of upgrading.
+pub async fn get_notification_socket_request(
+    State(state): State<ServerState>,
+    authenticated_account: AuthenticatedAccount,
+    websocket: WebSocketUpgrade,
+) -> impl IntoResponse {
+    let receiver = state.notification_sender.subscribe();
+
+    websocket.on_upgrade(move |socket| {
+        forward_notifications(
+            socket,
+            authenticated_account.account.id,
+            receiver,
+            state.shutdown.clone(),
+        )
+    })
+}
+
+/// This function forwards every ```Notification``` addressed to the account specified in the ```account_id``` argument to the socket.
+/// It returns when either the socket or the notification channel gets closed, or when the server starts shutting down, in which case the socket is closed first.
+async fn forward_notifications(
+    mut socket: WebSocket,
+    account_id: i32,
+    mut receiver: broadcast::Receiver<Notification>,
+    shutdown: CancellationToken,
+) {
+    metrics::gauge!("websocket_connections").increment(1);
+
+    loop {
+        tokio::select! {
+            notification = receiver.recv() => match notification {
+                Ok(notification) if notification.account_id == account_id => {
+                    if socket.send(Message::Text(notification.to_string())).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+            _ = shutdown.cancelled() => {
+                let _ = socket
+                    .send(Message::Close(Some(CloseFrame {
+                        code: close_code::AWAY,
+                        reason: "The server is shutting down".into(),
+                    })))
+                    .await;
+
+                break;
+            }
+        }
+    }
+
+    metrics::gauge!("websocket_connections").decrement(1);
+}
+
+/// This function will return every category as a ```Json<Vec<Category>>```, which is cached and can be revalidated with its `ETag`
+#[utoipa::path(
+    get,
+    path = "/api/v1/categories",
+    tag = "catalog",
+    responses((status = OK, body = Vec<Category>))
+)]
+pub async fn get_categories_request(
+    State(state): State<ServerState>,
+) -> Result<CachedJson, StatusCode> {
+    state
+        .response_cache
+        .categories(state.pgconnection.clone())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// This function will return the attribute schema of the category specified in the path as a ```Json<Vec<CategoryAttribute>>```, which is cached and can be revalidated with its `ETag`
+/// The frontend can use this to render the attribute inputs of a listing in the category.
+#[utoipa::path(
+    get,
+    path = "/api/v1/categories/{category_id}/attributes",
+    tag = "catalog",
+    params(("category_id" = i32, Path, description = "The ID of the category")),
+    responses((status = OK, body = Vec<CategoryAttribute>))
+)]
+pub async fn get_category_attributes_request(
+    State(state): State<ServerState>,
+    Path(category_id): Path<i32>,
+) -> Result<CachedJson, StatusCode> {
+    state
+        .response_cache
+        .category_attributes(category_id, state.pgconnection.clone())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// This function will return suggestions for the text entered into the search bar as a ```Json<Vec<String>>```
+/// The text is passed in via the `q` query parameter, if it is shorter than ```SuggestionQuery::MIN_QUERY_LENGTH``` no suggestions are returned.
+#[utoipa::path(
+    get,
+    path = "/api/v1/search/suggestions",
+    tag = "catalog",
+    params(SuggestionQuery),
+    responses((status = OK, body = Vec<String>))
+)]
+pub async fn get_search_suggestions_request(
+    State(state): State<ServerState>,
+    Query(query): Query<SuggestionQuery>,
+) -> Result<Json<Vec<String>>, StatusCode> {
+    let text = query.q.trim().to_string();
+
+    if text.chars().count() < SuggestionQuery::MIN_QUERY_LENGTH {
+        return Ok(Json(Vec::new()));
+    }
+
+    let suggestions = lookup_search_suggestions(
+        text,
+        query.limit.clamp(1, SuggestionQuery::MAX_LIMIT),
+        state.pgconnection.clone(),
+    )
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(suggestions))
+}
+
+/// This function will return the exchange rate of every currency prices can be normalized from as a ```Json<Vec<ExchangeRateEntry>>```, which is cached and can be revalidated with its `ETag`
+#[utoipa::path(
+    get,
+    path = "/api/v1/exchange_rates",
+    tag = "catalog",
+    responses((status = OK, body = Vec<ExchangeRateEntry>))
+)]
+pub async fn get_exchange_rates_request(
+    State(state): State<ServerState>,
+) -> Result<CachedJson, StatusCode> {
+    state
+        .response_cache
+        .exchange_rates(state.pgconnection.clone())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// This function will return the block list of the logged in account as a ```Json<Vec<BlockedAccount>>```
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/blocks",
+    tag = "me",
+    security(("session_cookie" = [])),
+    responses(
+        (status = OK, body = Vec<BlockedAccount>),
+        (status = UNAUTHORIZED, description = "The `session_id` cookie is missing or invalid"),
+    )
+)]
+pub async fn get_blocks_request(
+    State(state): State<ServerState>,
+    authenticated_account: AuthenticatedAccount,
+) -> Result<Json<Vec<BlockedAccount>>, StatusCode> {
+    let blocked_accounts =
+        list_blocked_accounts(authenticated_account.account.id, state.pgconnection.clone())
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(blocked_accounts))
+}
+
+/// This function will add the account specified in the request to the block list of the logged in account.
+/// If the account tries to block itself or the blocked account doesnt exist it will return ```StatusCode::BAD_REQUEST```
+/// This route is deprecated in favor of ```get_v1_block_create_request```.
+pub async fn get_block_create_request(
+    state: State<ServerState>,
+    authenticated_account: AuthenticatedAccount,
+    Json(blocked_id): Json<i32>,
+) -> StatusCode {
+    get_v1_block_create_request(state, authenticated_account, Path(blocked_id)).await
+}
+
+/// This function will add the account specified in the path to the block list of the logged in account.
+/// If the account tries to block itself or the blocked account doesnt exist it will return ```StatusCode::BAD_REQUEST```
+#[utoipa::path(
+    put,
+    path = "/api/v1/me/blocks/{account_id}",
+    tag = "me",
+    security(("session_cookie" = [])),
+    params(("account_id" = i32, Path, description = "The ID of the blocked account")),
+    responses(
+        (status = CREATED, description = "The account has been blocked"),
+        (status = BAD_REQUEST, description = "The account is the logged in account itself or doesnt exist"),
+        (status = UNAUTHORIZED, description = "The `session_id` cookie is missing or invalid"),
+    )
+)]
+pub async fn get_v1_block_create_request(
+    State(state): State<ServerState>,
+    authenticated_account: AuthenticatedAccount,
+    Path(blocked_id): Path<i32>,
+) -> StatusCode {
+    match block_account(
+        authenticated_account.account.id,
+        blocked_id,
+        state.pgconnection.clone(),
+    ) {
+        Ok(_) => StatusCode::CREATED,
+        Err(_err) => StatusCode::BAD_REQUEST,
+    }
+}
+
+/// This function will remove the account specified in the request from the block list of the logged in account.
+/// If the account wasnt blocked it will return ```StatusCode::NOT_FOUND```
+/// This route is deprecated in favor of ```get_v1_block_delete_request```.
+pub async fn get_block_delete_request(
+    state: State<ServerState>,
+    authenticated_account: AuthenticatedAccount,
+    Json(blocked_id): Json<i32>,
+) -> StatusCode {
+    get_v1_block_delete_request(state, authenticated_account, Path(blocked_id)).await
+}
+
+/// This function will remove the account specified in the path from the block list of the logged in account.
+/// If the account wasnt blocked it will return ```StatusCode::NOT_FOUND```
+#[utoipa::path(
+    delete,
+    path = "/api/v1/me/blocks/{account_id}",
+    tag = "me",
+    security(("session_cookie" = [])),
+    params(("account_id" = i32, Path, description = "The ID of the blocked account")),
+    responses(
+        (status = OK, description = "The account has been unblocked"),
+        (status = UNAUTHORIZED, description = "The `session_id` cookie is missing or invalid"),
+        (status = NOT_FOUND, description = "The account wasnt blocked"),
+    )
+)]
+pub async fn get_v1_block_delete_request(
+    State(state): State<ServerState>,
+    authenticated_account: AuthenticatedAccount,
+    Path(blocked_id): Path<i32>,
+) -> StatusCode {
+    match unblock_account(
+        authenticated_account.account.id,
+        blocked_id,
+        state.pgconnection.clone(),
+    ) {
+        Ok(0) => StatusCode::NOT_FOUND,
+        Ok(_) => StatusCode::OK,
+        Err(_err) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// This function will report the target specified in the request on behalf of the logged in account.
+/// It can either return ```StatusCode::CREATED```: When the report has been added to the moderation queue
+/// Or return ```StatusCode::BAD_REQUEST```: When the target doesnt exist, is the reporter itself or has already been reported by the same account
+#[utoipa::path(
+    post,
+    path = "/api/v1/reports",
+    tag = "moderation",
+    security(("session_cookie" = [])),
+    request_body = ReportRequest,
+    responses(
+        (status = CREATED, description = "The report has been added to the moderation queue"),
+        (status = BAD_REQUEST, description = "The target doesnt exist, is the reporter itself or has already been reported by the same account"),
+        (status = UNAUTHORIZED, description = "The `session_id` cookie is missing or invalid"),
+    )
+)]
+pub async fn get_report_request(
+    State(state): State<ServerState>,
+    authenticated_account: AuthenticatedAccount,
+    Json(body): Json<ReportRequest>,
+) -> StatusCode {
+    let (target_kind, target_id) = (body.target_kind, body.target_id);
+
+    match create_report(authenticated_account.account.id, body, state.pgconnection.clone()) {
+        Ok(_) => {
+            // The report could have hidden the target automatically
+            state.response_cache.invalidate_target(target_kind, target_id);
+
+            StatusCode::CREATED
+        }
+        Err(_err) => StatusCode::BAD_REQUEST,
+    }
+}
+
+/// This function will return a page of the moderation queue as a ```Json<Vec<Report>>```
+/// The page can be specified with the `page` and `per_page` query parameters, this route is guarded by ```Permission::ModerateReports```.
+pub async fn get_moderation_queue_request(
+    State(state): State<ServerState>,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<Vec<Report>>, StatusCode> {
+    let reports = list_pending_reports(&page, state.pgconnection.clone())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(reports))
+}
+
+/// This function will take the moderation action specified in the request on behalf of the logged in moderator, this route is guarded by ```Permission::ModerateReports```.
+/// If the action has been taken it will return the audit log entry of it as a ```Json<ModerationLogEntry>```
+/// If the target doesnt exist or the action can not be taken by moderators (```ModerationAction::RevokeSessions``` is only taken by admins) it will return ```StatusCode::BAD_REQUEST```
+/// If the target is the moderator's own account or has ```Permission::ManageAccounts``` and the action is a ban it will return ```StatusCode::FORBIDDEN```
+pub async fn get_moderation_action_request(
+    State(state): State<ServerState>,
+    authenticated_account: AuthenticatedAccount,
+    client: ClientInfo,
+    Json(body): Json<ModerationRequest>,
+) -> Result<Json<ModerationLogEntry>, StatusCode> {
+    // Revoking the sessions of an account doesnt resolve any report, it is an account management action
+    if body.action == ModerationAction::RevokeSessions {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    authorize_moderation_action(&state, &authenticated_account, &body)?;
+
+    let moderation_log_entry =
+        moderate_target(authenticated_account.account.id, body, state.pgconnection.clone())
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    state
+        .response_cache
+        .invalidate_target(moderation_log_entry.target_kind, moderation_log_entry.target_id);
+
+    record_session_revocation(&state, &client, &moderation_log_entry);
+
+    Ok(Json(moderation_log_entry))
+}
 
-        //Check for the user's ```AuthorizedUser``` instance, if found return the ```AccountLookup``` instance of the account from the database.
-        if let Ok(Some(authenticated_user)) =
-            check_authenticated_account(state.pgconnection.clone(), &authorized_user)
-        {
-            Ok(Json(lookup_account_from_id(authenticated_user.account_id, state.pgconnection.clone()).map_err(|_| (jar.remove("session_id"), StatusCode::NOT_FOUND))?))
+/// This function checks whether the logged in account can take the moderation action in the request.
+/// Nobody can ban or log out their own account or an account with ```Permission::ManageAccounts```, so that the admins can not be locked out, these requests get ```StatusCode::FORBIDDEN```.
+fn authorize_moderation_action(
+    state: &ServerState,
+    authenticated_account: &AuthenticatedAccount,
+    request: &ModerationRequest,
+) -> Result<(), StatusCode> {
+    let locks_out = matches!(
+        request.action,
+        ModerationAction::Ban | ModerationAction::RevokeSessions
+    );
+
+    match request.target_kind {
+        TargetKind::Account if locks_out => {
+            if request.target_id == authenticated_account.account.id {
+                return Err(StatusCode::FORBIDDEN);
+            }
+
+            let target_permissions =
+                lookup_account_permissions(request.target_id, state.pgconnection.clone())
+                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+            if target_permissions.contains(&Permission::ManageAccounts) {
+                return Err(StatusCode::FORBIDDEN);
+            }
+
+            Ok(())
         }
-        else {
-            Err((jar.remove("session_id"), StatusCode::BAD_REQUEST))
+        TargetKind::Account => Ok(()),
+    }
+}
+
+/// This function records it in the audit log if the moderation action passed in has ended the sessions of an account.
+fn record_session_revocation(
+    state: &ServerState,
+    client: &ClientInfo,
+    moderation_log_entry: &ModerationLogEntry,
+) {
+    let revoked_sessions = matches!(
+        moderation_log_entry.action,
+        ModerationAction::Ban | ModerationAction::RevokeSessions
+    );
+
+    if moderation_log_entry.target_kind == TargetKind::Account && revoked_sessions {
+        let _ = record_audit_event(
+            NewAuditEvent {
+                account_id: Some(moderation_log_entry.target_id),
+                details: moderation_log_entry
+                    .moderator_id
+                    .map(|moderator_id| format!("Revoked by account {moderator_id}")),
+                ..NewAuditEvent::from_client(
+                    AuditEventType::SessionRevocation,
+                    AuditOutcome::Success,
+                    client,
+                )
+            },
+            state.pgconnection.clone(),
+        );
+    }
+}
+
+/// This function will return a page of the moderation audit log as a ```Json<Vec<ModerationLogEntry>>```
+/// The page can be specified with the `page` and `per_page` query parameters, this route is guarded by ```Permission::ModerateReports```.
+pub async fn get_moderation_log_request(
+    State(state): State<ServerState>,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<Vec<ModerationLogEntry>>, StatusCode> {
+    let moderation_log = list_moderation_log(&page, state.pgconnection.clone())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(moderation_log))
+}
+
+/// This function will set the exchange rate specified in the request, this route is guarded by ```Permission::ManageExchangeRates```.
+/// If the rate is not positive or the currency is the forint itself it will return ```StatusCode::BAD_REQUEST```
+pub async fn get_exchange_rate_update_request(
+    State(state): State<ServerState>,
+    Json(body): Json<ExchangeRateRequest>,
+) -> Result<Json<ExchangeRateEntry>, StatusCode> {
+    let exchange_rate = set_exchange_rate(body.currency, body.rate_to_huf, state.pgconnection.clone())
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    state.response_cache.invalidate_exchange_rates();
+
+    Ok(Json(exchange_rate))
+}
+
+/// This function will grant the role specified in the request to the account, this route is guarded by ```Permission::ManageRoles```.
+/// If either the account or the role doesnt exist it will return ```StatusCode::NOT_FOUND```
+pub async fn get_role_grant_request(
+    State(state): State<ServerState>,
+    Json(body): Json<RoleRequest>,
+) -> StatusCode {
+    match grant_role(&body.username, &body.role, state.pgconnection.clone()) {
+        Ok(_) => StatusCode::OK,
+        Err(_err) => StatusCode::NOT_FOUND,
+    }
+}
+
+/// This function will revoke the role specified in the request from the account, this route is guarded by ```Permission::ManageRoles```.
+/// If either the account or the role doesnt exist it will return ```StatusCode::NOT_FOUND```
+pub async fn get_role_revoke_request(
+    State(state): State<ServerState>,
+    Json(body): Json<RoleRequest>,
+) -> StatusCode {
+    match revoke_role(&body.username, &body.role, state.pgconnection.clone()) {
+        Ok(_) => StatusCode::OK,
+        Err(_err) => StatusCode::NOT_FOUND,
+    }
+}
+
+/// This function will return the permissions of the logged in account as a ```Json<Vec<Permission>>```
+/// The frontend uses this to decide which admin pages to show, the routes themselves are guarded by the backend.
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/permissions",
+    tag = "me",
+    security(("session_cookie" = [])),
+    responses(
+        (status = OK, body = Vec<Permission>),
+        (status = UNAUTHORIZED, description = "The `session_id` cookie is missing or invalid"),
+    )
+)]
+pub async fn get_account_permissions_request(
+    authenticated_account: AuthenticatedAccount,
+) -> Json<Vec<Permission>> {
+    Json(authenticated_account.permissions.into_iter().collect())
+}
+
+/// This function will return a page of the accounts whose username contains the `q` query parameter as a ```Json<Vec<AdminAccountLookup>>```
+/// The page can be specified with the `page` and `per_page` query parameters, this route is guarded by ```Permission::ManageAccounts```.
+pub async fn get_admin_account_search_request(
+    State(state): State<ServerState>,
+    Query(search): Query<AccountSearchQuery>,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<Vec<AdminAccountLookup>>, StatusCode> {
+    let accounts = search_accounts(search.q, &page, state.pgconnection.clone())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(accounts))
+}
+
+/// This function will suspend the account specified in the request and end every session of it, this route is guarded by ```Permission::ManageAccounts```.
+/// The suspension is recorded in the moderation audit log as a ban, the entry is returned as a ```Json<ModerationLogEntry>```
+/// If the account doesnt exist it will return ```StatusCode::NOT_FOUND```, if it is the admin's own account or has ```Permission::ManageAccounts``` it will return ```StatusCode::FORBIDDEN```
+pub async fn get_admin_account_suspend_request(
+    State(state): State<ServerState>,
+    authenticated_account: AuthenticatedAccount,
+    client: ClientInfo,
+    Json(body): Json<AccountActionRequest>,
+) -> Result<Json<ModerationLogEntry>, StatusCode> {
+    take_admin_account_action(&state, &authenticated_account, &client, ModerationAction::Ban, body)
+}
+
+/// This function will lift the suspension of the account specified in the request, this route is guarded by ```Permission::ManageAccounts```.
+/// The account is restored in the moderation audit log, the entry is returned as a ```Json<ModerationLogEntry>```
+/// If the account doesnt exist it will return ```StatusCode::NOT_FOUND```
+pub async fn get_admin_account_unsuspend_request(
+    State(state): State<ServerState>,
+    authenticated_account: AuthenticatedAccount,
+    client: ClientInfo,
+    Json(body): Json<AccountActionRequest>,
+) -> Result<Json<ModerationLogEntry>, StatusCode> {
+    take_admin_account_action(&state, &authenticated_account, &client, ModerationAction::Restore, body)
+}
+
+/// This function will end every session of the account specified in the request, without suspending it, this route is guarded by ```Permission::ManageAccounts```.
+/// The revocation is recorded in the moderation audit log, the entry is returned as a ```Json<ModerationLogEntry>```
+/// If the account doesnt exist it will return ```StatusCode::NOT_FOUND```, if it is the admin's own account or has ```Permission::ManageAccounts``` it will return ```StatusCode::FORBIDDEN```
+pub async fn get_admin_session_revoke_request(
+    State(state): State<ServerState>,
+    authenticated_account: AuthenticatedAccount,
+    client: ClientInfo,
+    Json(body): Json<AccountActionRequest>,
+) -> Result<Json<ModerationLogEntry>, StatusCode> {
+    take_admin_account_action(
+        &state,
+        &authenticated_account,
+        &client,
+        ModerationAction::RevokeSessions,
+        body,
+    )
+}
+
+/// This function takes the moderation action passed in against the account specified in the request on behalf of the logged in admin.
+/// The action is checked with ```authorize_moderation_action```, so admins can not suspend or log out themselves or each other.
+fn take_admin_account_action(
+    state: &ServerState,
+    authenticated_account: &AuthenticatedAccount,
+    client: &ClientInfo,
+    action: ModerationAction,
+    request: AccountActionRequest,
+) -> Result<Json<ModerationLogEntry>, StatusCode> {
+    let request = ModerationRequest {
+        target_kind: TargetKind::Account,
+        target_id: request.account_id,
+        action,
+        note: request.note,
+    };
+
+    authorize_moderation_action(state, authenticated_account, &request)?;
+
+    let moderation_log_entry =
+        moderate_target(authenticated_account.account.id, request, state.pgconnection.clone())
+            .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    state
+        .response_cache
+        .invalidate_target(moderation_log_entry.target_kind, moderation_log_entry.target_id);
+
+    record_session_revocation(state, client, &moderation_log_entry);
+
+    Ok(Json(moderation_log_entry))
+}
+
+/// This function will create the category specified in the request and return it as a ```Json<Category>```, this route is guarded by ```Permission::ManageCategories```.
+/// If the name is empty or already taken, or the parent category doesnt exist it will return ```StatusCode::BAD_REQUEST```
+pub async fn get_admin_category_create_request(
+    State(state): State<ServerState>,
+    Json(body): Json<NewCategory>,
+) -> Result<Json<Category>, StatusCode> {
+    let category =
+        create_category(body, state.pgconnection.clone()).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    state.response_cache.invalidate_categories();
+
+    Ok(Json(category))
+}
+
+/// This function will delete the category specified in the request together with its subcategories and attributes, this route is guarded by ```Permission::ManageCategories```.
+/// If the category doesnt exist it will return ```StatusCode::NOT_FOUND```
+pub async fn get_admin_category_delete_request(
+    State(state): State<ServerState>,
+    Json(category_id): Json<i32>,
+) -> StatusCode {
+    match delete_category(category_id, state.pgconnection.clone()) {
+        Ok(0) => StatusCode::NOT_FOUND,
+        Ok(_) => {
+            state.response_cache.invalidate_categories();
+
+            StatusCode::OK
         }
+        Err(_err) => StatusCode::INTERNAL_SERVER_ERROR,
     }
-    else {
-        Err((jar.remove("session_id"), StatusCode::CONTINUE))
+}
+
+/// This function will add the attribute specified in the request to the schema of its category and return it as a ```Json<CategoryAttribute>```, this route is guarded by ```Permission::ManageCategories```.
+/// If the attribute is invalid, the category doesnt exist or already has an attribute with the same key it will return ```StatusCode::BAD_REQUEST```
+pub async fn get_admin_category_attribute_create_request(
+    State(state): State<ServerState>,
+    Json(body): Json<NewCategoryAttribute>,
+) -> Result<Json<CategoryAttribute>, StatusCode> {
+    let category_attribute = create_category_attribute(body, state.pgconnection.clone())
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    state.response_cache.invalidate_category_attributes();
+
+    Ok(Json(category_attribute))
+}
+
+/// This function will delete the category attribute specified in the request, this route is guarded by ```Permission::ManageCategories```.
+/// If the attribute doesnt exist it will return ```StatusCode::NOT_FOUND```
+pub async fn get_admin_category_attribute_delete_request(
+    State(state): State<ServerState>,
+    Json(attribute_id): Json<i32>,
+) -> StatusCode {
+    match delete_category_attribute(attribute_id, state.pgconnection.clone()) {
+        Ok(0) => StatusCode::NOT_FOUND,
+        Ok(_) => {
+            state.response_cache.invalidate_category_attributes();
+
+            StatusCode::OK
+        }
+        Err(_err) => StatusCode::INTERNAL_SERVER_ERROR,
     }
 }
 
+/// This function will return the statistics of the site as a ```Json<SiteStatistics>```, this route is guarded by ```Permission::ViewStatistics```.
+pub async fn get_admin_statistics_request(
+    State(state): State<ServerState>,
+) -> Result<Json<SiteStatistics>, StatusCode> {
+    let statistics = lookup_site_statistics(state.pgconnection.clone())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(statistics))
+}
+
+/// This function will return a page of the audit log as a ```Json<Vec<AuditEvent>>```, this route is guarded by ```Permission::ViewAuditLog```.
+/// The events can be filtered with the `account_id` and `event_type` query parameters, the page can be specified with the `page` and `per_page` query parameters.
+pub async fn get_admin_audit_events_request(
+    State(state): State<ServerState>,
+    Query(query): Query<AuditEventQuery>,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<Vec<AuditEvent>>, StatusCode> {
+    let audit_events = list_audit_events(&query, &page, state.pgconnection.clone())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(audit_events))
+}
+
 pub fn get_claims_from_str(
     encrypted_string: &str,
     secret: &[u8],
@@ -523,4 +3839,16 @@ pub async fn account_redirecting(
     }
 
     Ok(next.run(request).await)
+}
+
+/// This function marks the responses of the deprecated, unversioned API routes with the `Deprecation` header.
+/// Every one of these routes has a successor under `/api/v1`, which is described at `/api/v1/openapi.json`.
+pub async fn deprecated_route(request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+
+    response
+        .headers_mut()
+        .insert("Deprecation", HeaderValue::from_static("true"));
+
+    response
 }
\ No newline at end of file
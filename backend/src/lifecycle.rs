@@ -0,0 +1,172 @@
+//! This mod contains the health checks and the graceful shutdown of the server.
+//! `/healthz` tells whether the process is alive, `/readyz` tells whether it can serve requests, which requires the database to be reachable and fully migrated.
+
+use std::{future::IntoFuture, net::SocketAddr, time::Duration};
+
+use axum::{extract::State, http::StatusCode, serve, Json, Router};
+use axum_server::{tls_rustls::RustlsConfig, Handle};
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+use crate::{PgPool, ServerState};
+
+/// Every migration of the database, these are compared against the ones already run to tell whether the database is up to date.
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("../migrations");
+
+/// The time in-flight requests are given to finish after a shutdown has been requested, the remaining connections are dropped after it.
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The time the readiness check waits for a database connection before reporting the database as unreachable.
+const READINESS_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+/// This struct is returned by `/readyz`, it contains the result of every check the readiness depends on.
+pub struct Readiness {
+    /// Whether a connection could be taken from the pool
+    pub database: bool,
+    /// Whether every migration has been run on the database
+    pub migrations: bool,
+    /// Whether the server is shutting down, in which case it should not receive new requests
+    pub shutting_down: bool,
+}
+
+impl Readiness {
+    /// This function returns whether the server is ready to serve requests.
+    pub fn is_ready(&self) -> bool {
+        self.database && self.migrations && !self.shutting_down
+    }
+}
+
+/// This function checks whether the database is reachable and whether every migration has been run on it.
+/// It blocks for up to ```READINESS_TIMEOUT``` if the database is unreachable, so it should not be called from an async context directly.
+pub fn check_database_readiness(pgconnection: &PgPool) -> Readiness {
+    let Ok(mut conn) = pgconnection.get_timeout(READINESS_TIMEOUT) else {
+        return Readiness::default();
+    };
+
+    Readiness {
+        database: true,
+        migrations: matches!((*conn).has_pending_migration(MIGRATIONS), Ok(false)),
+        shutting_down: false,
+    }
+}
+
+/// This function will return ```StatusCode::OK``` as long as the server is able to handle requests at all.
+/// It doesnt check any dependency, so that the process doesnt get restarted when only the database is down.
+pub async fn get_healthz_request() -> StatusCode {
+    StatusCode::OK
+}
+
+/// This function will return the result of the readiness checks as a ```Json<Readiness>```
+/// It can either return ```StatusCode::OK```: When the server is ready to serve requests
+/// Or return ```StatusCode::SERVICE_UNAVAILABLE```: When the database is unreachable or not migrated, or the server is shutting down
+pub async fn get_readyz_request(State(state): State<ServerState>) -> (StatusCode, Json<Readiness>) {
+    let pgconnection = state.pgconnection.clone();
+
+    let readiness = Readiness {
+        shutting_down: state.shutdown.is_cancelled(),
+        ..tokio::task::spawn_blocking(move || check_database_readiness(&pgconnection))
+            .await
+            .unwrap_or_default()
+    };
+
+    let status_code = if readiness.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status_code, Json(readiness))
+}
+
+/// This function returns when the process receives either SIGINT (Ctrl+C) or SIGTERM.
+/// SIGTERM is only listened for on unix, as it doesnt exist on other platforms.
+pub async fn shutdown_signal() {
+    let interrupt = async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(_err) => std::future::pending::<()>().await,
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => {},
+        _ = terminate => {},
+    }
+}
+
+/// This function serves the app on the listener until the ```shutdown``` token gets cancelled.
+/// After the cancellation no new connections are accepted, the in-flight requests are given ```timeout``` to finish, then the remaining connections are dropped.
+/// The notification WebSockets listen for the same token, so they get closed as soon as the shutdown starts.
+pub async fn serve_with_graceful_shutdown(
+    listener: TcpListener,
+    app: Router,
+    shutdown: CancellationToken,
+    timeout: Duration,
+) -> anyhow::Result<()> {
+    // The client's address is recorded in the audit log
+    let server = serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown.clone().cancelled_owned())
+    .into_future();
+
+    let timed_out = async {
+        shutdown.cancelled().await;
+        tokio::time::sleep(timeout).await;
+    };
+
+    tokio::select! {
+        result = server => Ok(result?),
+        _ = timed_out => {
+            tracing::warn!(?timeout, "Requests were still in flight when the shutdown timed out");
+
+            Ok(())
+        }
+    }
+}
+
+/// This function serves the app over TLS on the listener until the ```shutdown``` token gets cancelled, the same way ```serve_with_graceful_shutdown``` does.
+/// The certificate used for new connections can be swapped by reloading the ```RustlsConfig```.
+pub async fn serve_tls_with_graceful_shutdown(
+    listener: TcpListener,
+    app: Router,
+    config: RustlsConfig,
+    shutdown: CancellationToken,
+    timeout: Duration,
+) -> anyhow::Result<()> {
+    let handle = Handle::new();
+
+    tokio::spawn({
+        let handle = handle.clone();
+
+        async move {
+            shutdown.cancelled().await;
+
+            handle.graceful_shutdown(Some(timeout));
+        }
+    });
+
+    // The client's address is recorded in the audit log
+    axum_server::from_tcp_rustls(listener.into_std()?, config)
+        .handle(handle)
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .await?;
+
+    Ok(())
+}
@@ -1,69 +1,354 @@
 use axum::{
-    middleware::{self}, response::{Html, IntoResponse}, routing::{get, post}, serve, Router
+    middleware::{self},
+    routing::{get, post, put},
+    serve, Router,
 };
 use backend::{
-    account_redirecting, establish_server_state, get_account_id_account_request, get_account_login_request, get_account_register_request, get_cookie_account_request
+    account_redirecting,
+    auth::permission_guard,
+    cache::not_modified,
+    csrf::csrf_protection,
+    db_types::safe_types::Permission,
+    deprecated_route, establish_server_state, get_account_id_account_request,
+    get_account_login_request, get_account_permissions_request, get_account_register_request,
+    get_admin_account_search_request, get_admin_account_suspend_request,
+    get_admin_account_unsuspend_request, get_admin_audit_events_request,
+    get_admin_category_attribute_create_request, get_admin_category_attribute_delete_request,
+    get_admin_category_create_request, get_admin_category_delete_request,
+    get_admin_session_revoke_request, get_admin_statistics_request, get_block_create_request,
+    get_block_delete_request, get_blocks_request, get_categories_request,
+    get_category_attributes_request, get_cookie_account_request, get_exchange_rate_update_request,
+    get_exchange_rates_request, get_moderation_action_request, get_moderation_log_request,
+    get_moderation_queue_request, get_notification_socket_request, get_notifications_read_request,
+    get_notifications_request, get_report_request, get_role_grant_request, get_role_revoke_request,
+    get_saved_search_create_request, get_saved_search_delete_request,
+    get_saved_search_list_request, get_saved_search_update_request, get_search_suggestions_request,
+    get_unread_notification_count_request, get_v1_account_request, get_v1_block_create_request,
+    get_v1_block_delete_request, get_v1_saved_search_delete_request,
+    get_v1_saved_search_update_request,
+    jobs::{start_job_workers, worker_count_from_env},
+    lifecycle::{
+        get_healthz_request, get_readyz_request, serve_tls_with_graceful_shutdown,
+        serve_with_graceful_shutdown, shutdown_signal, SHUTDOWN_TIMEOUT,
+    },
+    openapi::ApiDoc,
+    security::{allowed_origins_from_env, cors_layer, security_headers},
+    spa::{dist_dir_from_env, spa_router},
+    telemetry::{
+        init_tracing, install_metrics_recorder, make_request_span, metrics_address, metrics_router,
+        track_http_metrics, REQUEST_ID_HEADER,
+    },
+    tls::{hsts_layer, redirect_router, TlsSettings},
 };
-use reqwest::{Method, StatusCode};
-use std::path::PathBuf;
-use tokio::{fs, net::TcpListener};
-use tower::util::ServiceExt;
+use reqwest::header::{COOKIE, SET_COOKIE};
+use std::future::IntoFuture;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use tower::ServiceBuilder;
 use tower_http::{
-    cors::{Any, CorsLayer},
-    services::ServeDir,
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    sensitive_headers::SetSensitiveHeadersLayer,
+    trace::{DefaultOnResponse, TraceLayer},
 };
+use tracing::Level;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    init_tracing()?;
+
+    let metrics_handle = install_metrics_recorder()?;
+
+    let tls_settings = TlsSettings::from_env()?;
+
     let listener = TcpListener::bind("[::]:3004").await?;
 
     let state = establish_server_state()?;
 
-    let cors = CorsLayer::new()
-        .allow_methods([Method::GET, Method::POST, Method::HEAD])
-        .allow_origin(Any);
+    let job_workers = start_job_workers(state.clone(), worker_count_from_env()?);
+
+    let shutdown = state.shutdown.clone();
+
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+
+        async move {
+            shutdown_signal().await;
+
+            tracing::info!("Shutting down");
+
+            shutdown.cancel();
+        }
+    });
+
+    // The metrics are served on their own port, so that they are never exposed together with the public API
+    let metrics_listener = TcpListener::bind(metrics_address()?).await?;
+    let metrics_app = metrics_router(metrics_handle, state.pgconnection.clone());
+
+    tokio::spawn(async move { serve(metrics_listener, metrics_app).await });
+
+    let allowed_origins = allowed_origins_from_env()?;
+    let cors = cors_layer(allowed_origins.clone());
+
+    let spa = spa_router(dist_dir_from_env()).await;
 
     let app = Router::new()
         //Define service
-        .fallback_service(get(|req| async move {
-            let res = ServeDir::new("C:\\Users\\marci\\Desktop\\hasznalt\\frontend\\dist")
-                .oneshot(req)
-                .await
-                .unwrap();
-            let status = res.status();
-            match status {
-                StatusCode::NOT_FOUND => {
-                    let index_path =
-                        PathBuf::from("C:\\Users\\marci\\Desktop\\hasznalt\\frontend\\dist")
-                            .join("index.html");
-                    fs::read_to_string(index_path)
-                        .await
-                        .map(|index_content| (StatusCode::OK, Html(index_content)).into_response())
-                        .unwrap_or_else(|_| {
-                            (StatusCode::INTERNAL_SERVER_ERROR, "index.html not found")
-                                .into_response()
-                        })
-                }
-
-                // path was found as a file in the static dir
-                _ => res.into_response(),
-            }
-        }))
+        .fallback_service(spa)
+        /*
+            Define health checks
+        */
+        .route("/healthz", get(get_healthz_request))
+        .route("/readyz", get(get_readyz_request))
         /*
             Define api routes
         */
-        .route("/api/register", post(get_account_register_request))
-        .route("/api/login", post(get_account_login_request))
-        .route("/api/id_lookup", post(get_account_id_account_request))
-        .route("/api/account", post(get_cookie_account_request))
+        .merge(
+            Router::new()
+                .route("/api/v1/accounts", post(get_account_register_request))
+                .route("/api/v1/accounts/:id", get(get_v1_account_request))
+                .route("/api/v1/sessions", post(get_account_login_request))
+                .route("/api/v1/me", get(get_cookie_account_request))
+                .route("/api/v1/me/permissions", get(get_account_permissions_request))
+                .route(
+                    "/api/v1/me/saved_searches",
+                    get(get_saved_search_list_request).post(get_saved_search_create_request),
+                )
+                .route(
+                    "/api/v1/me/saved_searches/:id",
+                    put(get_v1_saved_search_update_request)
+                        .delete(get_v1_saved_search_delete_request),
+                )
+                .route("/api/v1/me/notifications", get(get_notifications_request))
+                .route(
+                    "/api/v1/me/notifications/unread_count",
+                    get(get_unread_notification_count_request),
+                )
+                .route(
+                    "/api/v1/me/notifications/read",
+                    post(get_notifications_read_request),
+                )
+                .route(
+                    "/api/v1/me/notifications/ws",
+                    get(get_notification_socket_request),
+                )
+                .route("/api/v1/me/blocks", get(get_blocks_request))
+                .route(
+                    "/api/v1/me/blocks/:account_id",
+                    put(get_v1_block_create_request).delete(get_v1_block_delete_request),
+                )
+                .route("/api/v1/categories", get(get_categories_request))
+                .route(
+                    "/api/v1/categories/:category_id/attributes",
+                    get(get_category_attributes_request),
+                )
+                .route(
+                    "/api/v1/search/suggestions",
+                    get(get_search_suggestions_request),
+                )
+                .route("/api/v1/exchange_rates", get(get_exchange_rates_request))
+                .route("/api/v1/reports", post(get_report_request))
+                .merge(
+                    SwaggerUi::new("/api/v1/docs").url("/api/v1/openapi.json", ApiDoc::openapi()),
+                ),
+        )
+        /*
+            Deprecated aliases of the versioned routes above
+        */
+        .merge(
+            Router::new()
+                .route("/api/register", post(get_account_register_request))
+                .route("/api/login", post(get_account_login_request))
+                .route("/api/id_lookup", post(get_account_id_account_request))
+                .route("/api/account", post(get_cookie_account_request))
+                .route("/api/account/permissions", get(get_account_permissions_request))
+                .route("/api/saved_search/create", post(get_saved_search_create_request))
+                .route("/api/saved_search/list", post(get_saved_search_list_request))
+                .route("/api/saved_search/update", post(get_saved_search_update_request))
+                .route("/api/saved_search/delete", post(get_saved_search_delete_request))
+                .route("/api/notifications", get(get_notifications_request))
+                .route(
+                    "/api/notifications/unread_count",
+                    get(get_unread_notification_count_request),
+                )
+                .route("/api/notifications/read", post(get_notifications_read_request))
+                .route("/api/notifications/ws", get(get_notification_socket_request))
+                .route("/api/categories", get(get_categories_request))
+                .route(
+                    "/api/categories/:category_id/attributes",
+                    get(get_category_attributes_request),
+                )
+                .route("/api/search/suggest", get(get_search_suggestions_request))
+                .route("/api/exchange_rates", get(get_exchange_rates_request))
+                .route("/api/report", post(get_report_request))
+                .route("/api/blocks", get(get_blocks_request))
+                .route("/api/blocks/create", post(get_block_create_request))
+                .route("/api/blocks/delete", post(get_block_delete_request))
+                .route_layer(middleware::from_fn(deprecated_route)),
+        )
+        .merge(
+            Router::new()
+                .route("/api/moderation/reports", get(get_moderation_queue_request))
+                .route("/api/moderation/actions", post(get_moderation_action_request))
+                .route("/api/moderation/log", get(get_moderation_log_request))
+                .route_layer(middleware::from_fn_with_state(
+                    (state.clone(), Permission::ModerateReports),
+                    permission_guard,
+                )),
+        )
+        .merge(
+            Router::new()
+                .route(
+                    "/api/admin/exchange_rates",
+                    post(get_exchange_rate_update_request),
+                )
+                .route_layer(middleware::from_fn_with_state(
+                    (state.clone(), Permission::ManageExchangeRates),
+                    permission_guard,
+                )),
+        )
+        .merge(
+            Router::new()
+                .route("/api/admin/roles/grant", post(get_role_grant_request))
+                .route("/api/admin/roles/revoke", post(get_role_revoke_request))
+                .route_layer(middleware::from_fn_with_state(
+                    (state.clone(), Permission::ManageRoles),
+                    permission_guard,
+                )),
+        )
+        .merge(
+            Router::new()
+                .route("/api/admin/accounts", get(get_admin_account_search_request))
+                .route(
+                    "/api/admin/accounts/suspend",
+                    post(get_admin_account_suspend_request),
+                )
+                .route(
+                    "/api/admin/accounts/unsuspend",
+                    post(get_admin_account_unsuspend_request),
+                )
+                .route(
+                    "/api/admin/accounts/sessions/revoke",
+                    post(get_admin_session_revoke_request),
+                )
+                .route_layer(middleware::from_fn_with_state(
+                    (state.clone(), Permission::ManageAccounts),
+                    permission_guard,
+                )),
+        )
+        .merge(
+            Router::new()
+                .route(
+                    "/api/admin/categories/create",
+                    post(get_admin_category_create_request),
+                )
+                .route(
+                    "/api/admin/categories/delete",
+                    post(get_admin_category_delete_request),
+                )
+                .route(
+                    "/api/admin/categories/attributes/create",
+                    post(get_admin_category_attribute_create_request),
+                )
+                .route(
+                    "/api/admin/categories/attributes/delete",
+                    post(get_admin_category_attribute_delete_request),
+                )
+                .route_layer(middleware::from_fn_with_state(
+                    (state.clone(), Permission::ManageCategories),
+                    permission_guard,
+                )),
+        )
+        .merge(
+            Router::new()
+                .route("/api/admin/audit_events", get(get_admin_audit_events_request))
+                .route_layer(middleware::from_fn_with_state(
+                    (state.clone(), Permission::ViewAuditLog),
+                    permission_guard,
+                )),
+        )
+        .merge(
+            Router::new()
+                .route("/api/admin/stats", get(get_admin_statistics_request))
+                .route_layer(middleware::from_fn_with_state(
+                    (state.clone(), Permission::ViewStatistics),
+                    permission_guard,
+                )),
+        )
         .layer(middleware::from_fn_with_state(
             state.clone(),
             account_redirecting,
         ))
+        .layer(middleware::from_fn_with_state(
+            allowed_origins,
+            csrf_protection,
+        ))
+        .layer(middleware::from_fn(not_modified))
+        .layer(middleware::from_fn(track_http_metrics))
+        .layer(middleware::from_fn(security_headers))
         .layer(cors)
+        .layer(
+            ServiceBuilder::new()
+                .layer(SetRequestIdLayer::new(REQUEST_ID_HEADER, MakeRequestUuid))
+                .layer(SetSensitiveHeadersLayer::new([COOKIE, SET_COOKIE]))
+                .layer(
+                    TraceLayer::new_for_http()
+                        .make_span_with(make_request_span)
+                        .on_response(DefaultOnResponse::new().level(Level::INFO)),
+                )
+                .layer(PropagateRequestIdLayer::new(REQUEST_ID_HEADER)),
+        )
         .with_state(state);
 
-    serve(listener, app).await?;
+    tracing::info!(address = %listener.local_addr()?, "Listening");
+
+    let served = serve_app(listener, app, tls_settings, shutdown.clone()).await;
+
+    // The workers are stopped even if the server failed, so that they can finish the jobs they are running
+    shutdown.cancel();
+
+    // The jobs still running after the timeout are claimed again once their locks time out
+    let _ = tokio::time::timeout(SHUTDOWN_TIMEOUT, job_workers.join_all()).await;
+
+    served
+}
+
+/// This function serves the app on the listener until the ```shutdown``` token gets cancelled, over TLS if ```tls_settings``` is set.
+/// With TLS the requests sent to the redirect address over plain HTTP are redirected to the app.
+async fn serve_app(
+    listener: TcpListener,
+    app: Router,
+    tls_settings: Option<TlsSettings>,
+    shutdown: CancellationToken,
+) -> anyhow::Result<()> {
+    match tls_settings {
+        Some(tls_settings) => {
+            let tls_config = tls_settings.load_config().await?;
+
+            // The certificates are only reloaded while the watcher is alive
+            let _certificate_watcher = tls_settings.watch_certificates(tls_config.clone())?;
+
+            let redirect_listener = TcpListener::bind(tls_settings.redirect_address).await?;
+            let redirect_app = redirect_router(listener.local_addr()?.port());
+
+            tokio::spawn(
+                serve(redirect_listener, redirect_app)
+                    .with_graceful_shutdown(shutdown.clone().cancelled_owned())
+                    .into_future(),
+            );
+
+            serve_tls_with_graceful_shutdown(
+                listener,
+                app.layer(hsts_layer()),
+                tls_config,
+                shutdown,
+                SHUTDOWN_TIMEOUT,
+            )
+            .await?;
+        }
+        None => serve_with_graceful_shutdown(listener, app, shutdown, SHUTDOWN_TIMEOUT).await?,
+    }
 
     Ok(())
 }
\ No newline at end of file
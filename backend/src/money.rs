@@ -0,0 +1,381 @@
+//! This mod contains the types used for representing prices.
+//! Amounts are always stored as an integer number of minor units (e.g. euro cents) paired with an explicit currency, floats are never used.
+
+use std::{fmt::Display, io::Write, marker::PhantomData};
+
+use diesel::{
+    deserialize::{self, FromSql, FromSqlRow},
+    expression::AsExpression,
+    pg::{Pg, PgValue},
+    serialize::{self, IsNull, Output, ToSql},
+    sql_types::Text,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// The number of units an exchange rate is scaled by, exchange rates are stored in millionths of a forint.
+pub const EXCHANGE_RATE_SCALE: i64 = 1_000_000;
+
+/// This trait is implemented by the marker types of the supported currencies.
+/// It is used to make arithmetic between different currencies a compile time error.
+pub trait Currency {
+    /// The runtime representation of this currency
+    const CODE: CurrencyCode;
+}
+
+/// Marker type of the Hungarian forint
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Huf;
+
+impl Currency for Huf {
+    const CODE: CurrencyCode = CurrencyCode::Huf;
+}
+
+/// Marker type of the euro
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Eur;
+
+impl Currency for Eur {
+    const CODE: CurrencyCode = CurrencyCode::Eur;
+}
+
+#[derive(
+    AsExpression,
+    FromSqlRow,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    ToSchema,
+)]
+#[diesel(sql_type = diesel::sql_types::Text)]
+#[serde(rename_all = "UPPERCASE")]
+/// This enum is the runtime representation of a currency, this is what gets stored in the database and sent to clients.
+/// It is stored as its ISO 4217 code in the database.
+pub enum CurrencyCode {
+    /// Hungarian forint, this is the base currency every price is normalized to
+    Huf,
+    /// Euro
+    Eur,
+}
+
+impl CurrencyCode {
+    /// This function returns the ISO 4217 code of this currency.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CurrencyCode::Huf => "HUF",
+            CurrencyCode::Eur => "EUR",
+        }
+    }
+
+    /// This function returns the number of decimal digits a minor unit represents.
+    /// Please note that forint amounts are whole forints, as the fillér is not used in practice.
+    pub fn minor_digits(&self) -> u32 {
+        match self {
+            CurrencyCode::Huf => 0,
+            CurrencyCode::Eur => 2,
+        }
+    }
+
+    /// This function returns the symbol displayed after the amount.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            CurrencyCode::Huf => "Ft",
+            CurrencyCode::Eur => "€",
+        }
+    }
+
+    /// This function formats an amount of minor units of this currency following the Hungarian locale rules.
+    /// The thousands are separated by a non-breaking space, the decimal separator is a comma and the symbol follows the amount (e.g. `12 500 Ft`, `1 234,50 €`).
+    pub fn format_minor(&self, amount_minor: i64) -> String {
+        let divisor = 10_u64.pow(self.minor_digits());
+        let absolute_amount = amount_minor.unsigned_abs();
+
+        let whole_digits = (absolute_amount / divisor).to_string();
+
+        let mut formatted = String::new();
+
+        if amount_minor < 0 {
+            formatted.push('-');
+        }
+
+        for (index, digit) in whole_digits.chars().enumerate() {
+            if index != 0 && (whole_digits.len() - index).is_multiple_of(3) {
+                formatted.push('\u{a0}');
+            }
+
+            formatted.push(digit);
+        }
+
+        if self.minor_digits() != 0 {
+            formatted.push_str(&format!(
+                ",{:0width$}",
+                absolute_amount % divisor,
+                width = self.minor_digits() as usize
+            ));
+        }
+
+        formatted.push('\u{a0}');
+        formatted.push_str(self.symbol());
+
+        formatted
+    }
+}
+
+impl Display for CurrencyCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl ToSql<Text, Pg> for CurrencyCode {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        out.write_all(self.as_str().as_bytes())?;
+
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Pg> for CurrencyCode {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"HUF" => Ok(CurrencyCode::Huf),
+            b"EUR" => Ok(CurrencyCode::Eur),
+            _ => Err("Unrecognized currency".into()),
+        }
+    }
+}
+
+/// An amount of money in the currency ```C```, stored as an integer number of minor units.
+/// Only amounts of the same currency can be added or subtracted, mixing currencies has to go through ```Money::convert```.
+/// Every operation is checked, so an overflowing amount is never truncated or wrapped around.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money<C: Currency> {
+    amount_minor: i64,
+    currency: PhantomData<C>,
+}
+
+impl<C: Currency> Money<C> {
+    /// This function creates a new ```Money``` instance from an amount of minor units.
+    pub fn from_minor(amount_minor: i64) -> Self {
+        Self {
+            amount_minor,
+            currency: PhantomData,
+        }
+    }
+
+    /// This function returns the amount in minor units.
+    pub fn amount_minor(&self) -> i64 {
+        self.amount_minor
+    }
+
+    /// This function converts the amount to the currency ```D``` with the exchange rate passed in.
+    /// The result is rounded to the nearest minor unit, halves are rounded away from zero.
+    /// It returns ```None``` if the converted amount doesnt fit in an ```i64```.
+    pub fn convert<D: Currency>(self, rate: ExchangeRate<C, D>) -> Option<Money<D>> {
+        let scaled_amount = self.amount_minor as i128 * rate.scaled_rate as i128;
+        let scale = EXCHANGE_RATE_SCALE as i128;
+
+        // Round half away from zero
+        let rounded_amount = if scaled_amount >= 0 {
+            (scaled_amount + scale / 2) / scale
+        } else {
+            (scaled_amount - scale / 2) / scale
+        };
+
+        i64::try_from(rounded_amount).ok().map(Money::from_minor)
+    }
+
+    /// This function adds two amounts, returning ```None``` if the result would overflow.
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.amount_minor
+            .checked_add(rhs.amount_minor)
+            .map(Self::from_minor)
+    }
+
+    /// This function subtracts two amounts, returning ```None``` if the result would overflow.
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.amount_minor
+            .checked_sub(rhs.amount_minor)
+            .map(Self::from_minor)
+    }
+
+    /// This function negates the amount, returning ```None``` if the result would overflow.
+    pub fn checked_neg(self) -> Option<Self> {
+        self.amount_minor.checked_neg().map(Self::from_minor)
+    }
+}
+
+impl<C: Currency> Display for Money<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&C::CODE.format_minor(self.amount_minor))
+    }
+}
+
+impl<C: Currency> From<Money<C>> for Price {
+    fn from(money: Money<C>) -> Self {
+        Price {
+            amount_minor: money.amount_minor,
+            currency: C::CODE,
+        }
+    }
+}
+
+/// The rate a ```Money<F>``` can be converted to a ```Money<T>``` with.
+/// It contains how many millionths of a minor unit of ```T``` one minor unit of ```F``` is worth.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExchangeRate<F: Currency, T: Currency> {
+    scaled_rate: i64,
+    currencies: PhantomData<(F, T)>,
+}
+
+impl<F: Currency, T: Currency> ExchangeRate<F, T> {
+    /// This function creates a new ```ExchangeRate``` from the number of millionths of a minor unit of ```T``` one minor unit of ```F``` is worth.
+    pub fn from_scaled(scaled_rate: i64) -> Self {
+        Self {
+            scaled_rate,
+            currencies: PhantomData,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// A price with its currency only known at runtime, this is how prices are stored in the database and sent to clients.
+/// It has to be turned into a ```Money``` via ```Price::as_money``` before doing arithmetic with it.
+pub struct Price {
+    /// The amount in minor units of the currency
+    pub amount_minor: i64,
+    /// The currency of the price
+    pub currency: CurrencyCode,
+}
+
+impl Price {
+    /// This function returns the price as a ```Money<C>```, or ```None``` if the price is not in the currency ```C```.
+    pub fn as_money<C: Currency>(&self) -> Option<Money<C>> {
+        (self.currency == C::CODE).then(|| Money::from_minor(self.amount_minor))
+    }
+
+    /// This function normalizes the price to forints.
+    /// The exchange rate is only used if the price is not in forints already, it is the number of millionths of a forint one minor unit of the price's currency is worth.
+    /// It returns ```None``` if the amount in forints doesnt fit in an ```i64```.
+    pub fn to_huf(&self, scaled_rate_to_huf: i64) -> Option<Money<Huf>> {
+        match self.currency {
+            CurrencyCode::Huf => Some(Money::from_minor(self.amount_minor)),
+            CurrencyCode::Eur => Money::<Eur>::from_minor(self.amount_minor)
+                .convert(ExchangeRate::from_scaled(scaled_rate_to_huf)),
+        }
+    }
+}
+
+impl Display for Price {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.currency.format_minor(self.amount_minor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_thousands_with_non_breaking_spaces() {
+        assert_eq!(CurrencyCode::Huf.format_minor(0), "0\u{a0}Ft");
+        assert_eq!(CurrencyCode::Huf.format_minor(999), "999\u{a0}Ft");
+        assert_eq!(
+            CurrencyCode::Huf.format_minor(12_500),
+            "12\u{a0}500\u{a0}Ft"
+        );
+        assert_eq!(
+            CurrencyCode::Huf.format_minor(1_234_567),
+            "1\u{a0}234\u{a0}567\u{a0}Ft"
+        );
+        assert_eq!(
+            CurrencyCode::Eur.format_minor(123_450),
+            "1\u{a0}234,50\u{a0}€"
+        );
+        assert_eq!(CurrencyCode::Eur.format_minor(5), "0,05\u{a0}€");
+    }
+
+    #[test]
+    fn formats_negative_amounts() {
+        assert_eq!(
+            CurrencyCode::Huf.format_minor(-12_500),
+            "-12\u{a0}500\u{a0}Ft"
+        );
+        assert_eq!(CurrencyCode::Eur.format_minor(-5), "-0,05\u{a0}€");
+        assert_eq!(
+            CurrencyCode::Huf.format_minor(i64::MIN),
+            "-9\u{a0}223\u{a0}372\u{a0}036\u{a0}854\u{a0}775\u{a0}808\u{a0}Ft"
+        );
+    }
+
+    #[test]
+    fn conversion_rounds_halves_away_from_zero() {
+        // One cent is worth 4.5 forints
+        let rate = ExchangeRate::<Eur, Huf>::from_scaled(4_500_000);
+
+        assert_eq!(
+            Money::<Eur>::from_minor(1).convert(rate),
+            Some(Money::from_minor(5))
+        );
+        assert_eq!(
+            Money::<Eur>::from_minor(-1).convert(rate),
+            Some(Money::from_minor(-5))
+        );
+        assert_eq!(
+            Money::<Eur>::from_minor(2).convert(rate),
+            Some(Money::from_minor(9))
+        );
+        assert_eq!(
+            Money::<Eur>::from_minor(-2).convert(rate),
+            Some(Money::from_minor(-9))
+        );
+
+        // Just below a half is rounded towards zero
+        let rate = ExchangeRate::<Eur, Huf>::from_scaled(4_499_999);
+
+        assert_eq!(
+            Money::<Eur>::from_minor(1).convert(rate),
+            Some(Money::from_minor(4))
+        );
+        assert_eq!(
+            Money::<Eur>::from_minor(-1).convert(rate),
+            Some(Money::from_minor(-4))
+        );
+    }
+
+    #[test]
+    fn conversion_overflow_is_none() {
+        let rate = ExchangeRate::<Eur, Huf>::from_scaled(400 * EXCHANGE_RATE_SCALE);
+
+        assert_eq!(Money::<Eur>::from_minor(i64::MAX).convert(rate), None);
+        assert_eq!(Money::<Eur>::from_minor(i64::MIN).convert(rate), None);
+        assert_eq!(
+            Price {
+                amount_minor: i64::MAX,
+                currency: CurrencyCode::Eur
+            }
+            .to_huf(400 * EXCHANGE_RATE_SCALE),
+            None
+        );
+    }
+
+    #[test]
+    fn checked_arithmetic_overflow_is_none() {
+        let max = Money::<Huf>::from_minor(i64::MAX);
+        let min = Money::<Huf>::from_minor(i64::MIN);
+        let one = Money::<Huf>::from_minor(1);
+
+        assert_eq!(max.checked_add(one), None);
+        assert_eq!(min.checked_sub(one), None);
+        assert_eq!(min.checked_neg(), None);
+        assert_eq!(one.checked_add(one), Some(Money::from_minor(2)));
+        assert_eq!(one.checked_neg(), Some(Money::from_minor(-1)));
+    }
+}
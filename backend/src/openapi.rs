@@ -0,0 +1,59 @@
+//! This mod contains the OpenAPI document of the versioned API, which is served at `/api/v1/openapi.json`.
+//! The document is generated from the handlers under `/api/v1` and the types they take and return, so it can not drift from the implementation.
+
+use utoipa::{
+    openapi::security::{ApiKey, ApiKeyValue, SecurityScheme},
+    Modify, OpenApi,
+};
+
+/// The OpenAPI document of every route under `/api/v1`.
+/// The notification WebSocket (`/api/v1/me/notifications/ws`) is not part of it, as OpenAPI can not describe WebSockets.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Hasznalt API", version = "1.0.0"),
+    paths(
+        crate::get_account_register_request,
+        crate::get_account_login_request,
+        crate::get_v1_account_request,
+        crate::get_cookie_account_request,
+        crate::get_account_permissions_request,
+        crate::get_saved_search_list_request,
+        crate::get_saved_search_create_request,
+        crate::get_v1_saved_search_update_request,
+        crate::get_v1_saved_search_delete_request,
+        crate::get_notifications_request,
+        crate::get_unread_notification_count_request,
+        crate::get_notifications_read_request,
+        crate::get_blocks_request,
+        crate::get_v1_block_create_request,
+        crate::get_v1_block_delete_request,
+        crate::get_categories_request,
+        crate::get_category_attributes_request,
+        crate::get_search_suggestions_request,
+        crate::get_exchange_rates_request,
+        crate::get_report_request,
+    ),
+    modifiers(&SessionCookie),
+    tags(
+        (name = "accounts", description = "Registration, login and public account information"),
+        (name = "me", description = "Everything owned by the logged in account"),
+        (name = "catalog", description = "Categories, search suggestions and exchange rates"),
+        (name = "moderation", description = "Reporting abuse to the moderators"),
+    )
+)]
+pub struct ApiDoc;
+
+/// This struct adds the `session_id` cookie, which is set by logging in, to the security schemes of the document.
+struct SessionCookie;
+
+impl Modify for SessionCookie {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi
+            .components
+            .get_or_insert_with(Default::default)
+            .add_security_scheme(
+                "session_cookie",
+                SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new("session_id"))),
+            );
+    }
+}
@@ -1,11 +1,43 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    account_blocks (blocker_id, blocked_id) {
+        blocker_id -> Int4,
+        blocked_id -> Int4,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    account_roles (account_id, role_id) {
+        account_id -> Int4,
+        role_id -> Int4,
+    }
+}
+
 diesel::table! {
     accounts (id) {
         username -> Varchar,
         id -> Int4,
         passw -> Varchar,
         created_at -> Date,
+        hidden_at -> Nullable<Timestamp>,
+        hidden_until -> Nullable<Timestamp>,
+        banned_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    audit_events (id) {
+        id -> Int4,
+        account_id -> Nullable<Int4>,
+        username -> Nullable<Varchar>,
+        ip_address -> Nullable<Varchar>,
+        user_agent -> Nullable<Varchar>,
+        event_type -> Varchar,
+        outcome -> Varchar,
+        details -> Nullable<Varchar>,
+        created_at -> Timestamp,
     }
 }
 
@@ -17,4 +49,149 @@ diesel::table! {
     }
 }
 
-diesel::allow_tables_to_appear_in_same_query!(accounts, authorized_users,);
+diesel::table! {
+    categories (id) {
+        id -> Int4,
+        name -> Varchar,
+        parent_id -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    category_attributes (id) {
+        id -> Int4,
+        category_id -> Int4,
+        name -> Varchar,
+        label -> Varchar,
+        value_type -> Varchar,
+        unit -> Nullable<Varchar>,
+        allowed_values -> Nullable<Jsonb>,
+        required -> Bool,
+    }
+}
+
+diesel::table! {
+    exchange_rates (currency) {
+        currency -> Varchar,
+        rate_to_huf -> Int8,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    job_schedules (name) {
+        name -> Varchar,
+        next_run_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    jobs (id) {
+        id -> Int4,
+        kind -> Varchar,
+        payload -> Jsonb,
+        status -> Varchar,
+        attempts -> Int4,
+        max_attempts -> Int4,
+        run_at -> Timestamp,
+        locked_at -> Nullable<Timestamp>,
+        last_error -> Nullable<Varchar>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    moderation_log (id) {
+        id -> Int4,
+        moderator_id -> Nullable<Int4>,
+        action -> Varchar,
+        target_kind -> Varchar,
+        target_id -> Int4,
+        note -> Nullable<Varchar>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    notifications (id) {
+        id -> Int4,
+        account_id -> Int4,
+        kind -> Varchar,
+        title -> Varchar,
+        body -> Varchar,
+        link -> Nullable<Varchar>,
+        read_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    reports (id) {
+        id -> Int4,
+        reporter_id -> Int4,
+        target_kind -> Varchar,
+        target_id -> Int4,
+        reason -> Varchar,
+        details -> Nullable<Varchar>,
+        status -> Varchar,
+        created_at -> Timestamp,
+        resolved_by -> Nullable<Int4>,
+        resolved_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    role_permissions (role_id, permission) {
+        role_id -> Int4,
+        permission -> Varchar,
+    }
+}
+
+diesel::table! {
+    roles (id) {
+        id -> Int4,
+        name -> Varchar,
+    }
+}
+
+diesel::table! {
+    saved_searches (id) {
+        id -> Int4,
+        account_id -> Int4,
+        query -> Varchar,
+        category -> Nullable<Varchar>,
+        min_price -> Nullable<Int4>,
+        max_price -> Nullable<Int4>,
+        location -> Nullable<Varchar>,
+        alert_frequency -> Varchar,
+        last_alerted_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::joinable!(account_roles -> accounts (account_id));
+diesel::joinable!(account_roles -> roles (role_id));
+diesel::joinable!(category_attributes -> categories (category_id));
+diesel::joinable!(moderation_log -> accounts (moderator_id));
+diesel::joinable!(notifications -> accounts (account_id));
+diesel::joinable!(role_permissions -> roles (role_id));
+diesel::joinable!(saved_searches -> accounts (account_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    account_blocks,
+    account_roles,
+    accounts,
+    audit_events,
+    authorized_users,
+    categories,
+    category_attributes,
+    exchange_rates,
+    job_schedules,
+    jobs,
+    moderation_log,
+    notifications,
+    reports,
+    role_permissions,
+    roles,
+    saved_searches,
+);
@@ -0,0 +1,245 @@
+//! This mod contains the CORS policy and the security headers sent with every response.
+//! The origins allowed to make credentialed cross-origin requests are read from the `CORS_ALLOWED_ORIGINS` environment variable.
+
+use axum::{
+    extract::Request,
+    http::{
+        header::{
+            CONTENT_SECURITY_POLICY, CONTENT_TYPE, REFERRER_POLICY, X_CONTENT_TYPE_OPTIONS,
+            X_FRAME_OPTIONS,
+        },
+        HeaderName, HeaderValue, Method,
+    },
+    middleware::Next,
+    response::Response,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sha2::{Digest, Sha256};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::{csrf::CSRF_HEADER, telemetry::REQUEST_ID_HEADER};
+
+/// The origins allowed if the `CORS_ALLOWED_ORIGINS` environment variable is not set, these are the addresses of the Trunk dev server and of the backend itself.
+const DEFAULT_ALLOWED_ORIGINS: &str =
+    "http://127.0.0.1:1420,http://localhost:1420,http://[::1]:3004";
+
+/// The origins the frontend connects to besides its own, as it addresses the API (and the notification WebSocket) at `[::1]:3004` directly.
+const API_CONNECT_SOURCES: &str = "http://[::1]:3004 ws://[::1]:3004";
+
+/// The path the Swagger UI is served under, it needs inline styles, so it gets a relaxed policy.
+const SWAGGER_UI_PATH: &str = "/api/v1/docs";
+
+/// The `Permissions-Policy` header, which has no constant in ```axum::http::header```
+const PERMISSIONS_POLICY: HeaderName = HeaderName::from_static("permissions-policy");
+
+/// The value of the `Permissions-Policy` header, the site doesnt use any of these browser features.
+const PERMISSIONS_POLICY_VALUE: &str =
+    "camera=(), microphone=(), geolocation=(), payment=(), usb=()";
+
+/// This function reads the allowed origins from the `CORS_ALLOWED_ORIGINS` environment variable, which is a comma separated list (e.g. `https://hasznalt.hu,https://www.hasznalt.hu`).
+/// If the variable is not set ```DEFAULT_ALLOWED_ORIGINS``` is used.
+pub fn allowed_origins_from_env() -> anyhow::Result<Vec<HeaderValue>> {
+    let origins = match std::env::var("CORS_ALLOWED_ORIGINS") {
+        Ok(origins) => origins,
+        Err(std::env::VarError::NotPresent) => DEFAULT_ALLOWED_ORIGINS.to_string(),
+        Err(err) => return Err(err.into()),
+    };
+
+    origins
+        .split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .map(|origin| {
+            // Browsers send the origin without a trailing slash, so an allowlist entry with one would never match
+            anyhow::ensure!(
+                !origin.ends_with('/') && origin.contains("://"),
+                "Invalid origin in CORS_ALLOWED_ORIGINS: {origin}"
+            );
+
+            Ok(HeaderValue::from_str(origin)?)
+        })
+        .collect()
+}
+
+/// This function creates the CORS policy, only the origins passed in can make cross-origin requests, and they can send cookies with them.
+pub fn cors_layer(allowed_origins: Vec<HeaderValue>) -> CorsLayer {
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::list(allowed_origins))
+        .allow_credentials(true)
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::DELETE,
+            Method::HEAD,
+        ])
+        .allow_headers([CONTENT_TYPE, CSRF_HEADER])
+        .expose_headers([REQUEST_ID_HEADER])
+}
+
+/// This function builds the `Content-Security-Policy` of the site.
+/// The wasm bundle built by Trunk needs `'wasm-unsafe-eval'` to be compiled, and Trunk loads it with an inline script, which can be allowed by passing in its hash.
+fn content_security_policy(inline_script_hashes: &[String], allow_inline_styles: bool) -> String {
+    let script_sources = inline_script_hashes.iter().fold(
+        String::from("'self' 'wasm-unsafe-eval'"),
+        |sources, hash| format!("{sources} '{hash}'"),
+    );
+
+    let style_sources = if allow_inline_styles {
+        "'self' 'unsafe-inline'"
+    } else {
+        "'self'"
+    };
+
+    format!(
+        "default-src 'self'; script-src {script_sources}; style-src {style_sources}; img-src 'self' data:; connect-src 'self' {API_CONNECT_SOURCES}; object-src 'none'; base-uri 'self'; form-action 'self'; frame-ancestors 'none'"
+    )
+}
+
+/// This function returns the `Content-Security-Policy` the `index.html` built by Trunk should be served with.
+/// The hash of every inline script in the document is allowed, so that the script loading the wasm bundle can run.
+pub fn index_content_security_policy(index_html: &str) -> HeaderValue {
+    HeaderValue::from_str(&content_security_policy(
+        &inline_script_hashes(index_html),
+        false,
+    ))
+    .expect("The policy only contains visible ASCII characters")
+}
+
+/// This function returns the `sha256-<base64>` source expression of every inline `<script>` in the HTML document.
+fn inline_script_hashes(html: &str) -> Vec<String> {
+    let mut hashes = Vec::new();
+    let mut rest = html;
+
+    while let Some(script_start) = rest.find("<script") {
+        rest = &rest[script_start..];
+
+        let Some(tag_end) = rest.find('>') else {
+            break;
+        };
+        let Some(script_end) = rest.find("</script>") else {
+            break;
+        };
+
+        // Scripts with a `src` attribute are covered by `'self'`
+        if !has_src_attribute(&rest[..tag_end]) && tag_end < script_end {
+            let script = &rest[tag_end + 1..script_end];
+
+            hashes.push(format!(
+                "sha256-{}",
+                STANDARD.encode(Sha256::digest(script))
+            ));
+        }
+
+        rest = &rest[script_end + "</script>".len()..];
+    }
+
+    hashes
+}
+
+/// This function returns whether the opening tag passed in has a `src` attribute.
+/// The attribute name can be preceded by any whitespace (Trunk puts attributes on new lines too), and can have whitespace before its `=`.
+fn has_src_attribute(tag: &str) -> bool {
+    let tag = tag.to_ascii_lowercase();
+
+    tag.match_indices("src").any(|(index, name)| {
+        tag[..index].ends_with(|c: char| c.is_ascii_whitespace())
+            && tag[index + name.len()..].trim_start().starts_with('=')
+    })
+}
+
+/// This function adds the security headers to every response.
+/// The `Content-Security-Policy` is only added if the response doesnt have one already, as `index.html` is served with its own.
+pub async fn security_headers(request: Request, next: Next) -> Response {
+    let is_swagger_ui = request.uri().path().starts_with(SWAGGER_UI_PATH);
+
+    let mut response = next.run(request).await;
+
+    let headers = response.headers_mut();
+
+    if !headers.contains_key(CONTENT_SECURITY_POLICY) {
+        let policy = content_security_policy(&[], is_swagger_ui);
+
+        headers.insert(
+            CONTENT_SECURITY_POLICY,
+            HeaderValue::from_str(&policy)
+                .expect("The policy only contains visible ASCII characters"),
+        );
+    }
+
+    headers.insert(X_FRAME_OPTIONS, HeaderValue::from_static("DENY"));
+    headers.insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    headers.insert(
+        REFERRER_POLICY,
+        HeaderValue::from_static("strict-origin-when-cross-origin"),
+    );
+    headers.insert(
+        PERMISSIONS_POLICY,
+        HeaderValue::from_static(PERMISSIONS_POLICY_VALUE),
+    );
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `index.html` Trunk builds from `frontend/index.html`
+    const TRUNK_INDEX_HTML: &str = include_str!("../tests/fixtures/trunk_index.html");
+
+    /// The hash of the inline script Trunk loads the wasm bundle with
+    const TRUNK_LOADER_HASH: &str = "sha256-WB5udE2zjBzUK3G7lBaTQIVWhOlziZ+E6JOs9TdZqbs=";
+
+    #[test]
+    fn trunk_loader_script_is_hashed() {
+        assert_eq!(inline_script_hashes(TRUNK_INDEX_HTML), [TRUNK_LOADER_HASH]);
+
+        let policy = index_content_security_policy(TRUNK_INDEX_HTML);
+        let policy = policy.to_str().unwrap();
+
+        assert!(policy.contains(&format!(
+            "script-src 'self' 'wasm-unsafe-eval' '{TRUNK_LOADER_HASH}';"
+        )));
+        assert!(policy.contains("style-src 'self';"));
+    }
+
+    #[test]
+    fn scripts_with_src_are_not_hashed() {
+        for tag in [
+            "<script src=\"/app.js\">",
+            "<script type=\"module\"\n  src=\"/app.js\">",
+            "<script type=\"module\"\tsrc=\"/app.js\">",
+            "<script\nsrc=\"/app.js\">",
+            "<script SRC = \"/app.js\">",
+        ] {
+            assert!(has_src_attribute(tag), "{tag:?}");
+            assert!(inline_script_hashes(&format!("{tag}</script>")).is_empty());
+        }
+
+        for tag in [
+            "<script>",
+            "<script type=\"module\">",
+            "<script data-src=\"/app.js\">",
+            "<script srcset=\"/app.js\">",
+        ] {
+            assert!(!has_src_attribute(tag), "{tag:?}");
+        }
+    }
+
+    #[test]
+    fn every_inline_script_is_hashed() {
+        let html = "<script>console.log(1)</script><script src=\"/app.js\"></script>\n<script type=\"module\">\nlet loaded = true;\n</script>";
+
+        assert_eq!(
+            inline_script_hashes(html),
+            [
+                "sha256-CihokcEcBW4atb/CW/XWsvWwbTjqwQlE9nj9ii5ww5M=",
+                "sha256-u9IodWMWQ+DYpenjAS3pteZQ5nI1jk6gysLclIc3oOs="
+            ]
+        );
+        assert!(inline_script_hashes("<p>No scripts</p>").is_empty());
+        // An unclosed script is not hashed
+        assert!(inline_script_hashes("<script>console.log(1)").is_empty());
+    }
+}
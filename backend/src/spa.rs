@@ -0,0 +1,424 @@
+//! This mod contains the service serving the frontend built by Trunk as a single page application.
+//! Files of the dist directory are served as they are, preferring their precompressed `.br` and `.gz` variants, every other path gets `index.html` so that the frontend router can handle it.
+//! The dist directory is read from the `FRONTEND_DIST_DIR` environment variable.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use axum::{
+    body::Bytes,
+    extract::{Request, State},
+    http::{
+        header::{
+            ACCEPT_ENCODING, CACHE_CONTROL, CONTENT_LENGTH, CONTENT_SECURITY_POLICY, ETAG,
+            IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, VARY,
+        },
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{Html, IntoResponse, Response},
+    routing::{any, get, MethodRouter},
+    Router,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use tower::util::ServiceExt;
+use tower_http::services::ServeDir;
+
+use crate::{cache::etag_matches, security::index_content_security_policy};
+
+/// The dist directory used if the `FRONTEND_DIST_DIR` environment variable is not set, which is where Trunk builds the frontend of this repository.
+const DEFAULT_DIST_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../frontend/dist");
+
+/// The `Cache-Control` of the files with a content hash in their name, their content never changes, as a new build gets a new name.
+const IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// The `Cache-Control` of `index.html` and the files without a content hash, they can be cached but have to be revalidated before every use.
+const REVALIDATE_CACHE_CONTROL: &str = "no-cache";
+
+/// The format of the dates in the `Last-Modified` and `If-Modified-Since` headers
+const HTTP_DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// This function reads the dist directory from the `FRONTEND_DIST_DIR` environment variable.
+/// If the variable is not set ```DEFAULT_DIST_DIR``` is used.
+pub fn dist_dir_from_env() -> PathBuf {
+    std::env::var_os("FRONTEND_DIST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_DIST_DIR))
+}
+
+/// The `index.html` built by Trunk, it is read once at startup, as it only changes when the frontend is redeployed.
+#[derive(Debug)]
+struct IndexDocument {
+    html: Bytes,
+    content_security_policy: HeaderValue,
+    etag: HeaderValue,
+    last_modified: Option<DateTime<Utc>>,
+}
+
+impl IndexDocument {
+    /// This function reads `index.html` from the dist directory, and computes the headers it is served with.
+    async fn load(dist_dir: &Path) -> anyhow::Result<Self> {
+        let index_path = dist_dir.join("index.html");
+
+        let html = match tokio::fs::read_to_string(&index_path).await {
+            Ok(html) => html,
+            Err(err) => anyhow::bail!(
+                "Failed to read {}, build the frontend with `trunk build` or set FRONTEND_DIST_DIR: {err}",
+                index_path.display()
+            ),
+        };
+
+        let last_modified = tokio::fs::metadata(&index_path)
+            .await?
+            .modified()
+            .ok()
+            .map(truncate_to_seconds);
+
+        let etag = HeaderValue::from_str(&format!(
+            "\"{}\"",
+            URL_SAFE_NO_PAD.encode(Sha256::digest(&html))
+        ))?;
+
+        Ok(Self {
+            content_security_policy: index_content_security_policy(&html),
+            html: Bytes::from(html),
+            etag,
+            last_modified,
+        })
+    }
+
+    /// This function returns the headers both the full and the `304 Not Modified` responses carry.
+    fn validator_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+
+        headers.insert(
+            CACHE_CONTROL,
+            HeaderValue::from_static(REVALIDATE_CACHE_CONTROL),
+        );
+        headers.insert(ETAG, self.etag.clone());
+
+        if let Some(last_modified) = self.last_modified {
+            if let Ok(last_modified) = HeaderValue::from_str(&format_http_date(last_modified)) {
+                headers.insert(LAST_MODIFIED, last_modified);
+            }
+        }
+
+        headers
+    }
+}
+
+/// This function creates the router serving the frontend from the dist directory, it should be used as the fallback of the app.
+/// Unknown paths under `/api` get ```StatusCode::NOT_FOUND``` instead of `index.html`, so that API clients get a proper error.
+/// If the dist directory doesnt contain an `index.html` a warning is logged and every other path gets ```StatusCode::SERVICE_UNAVAILABLE```, so that the API can still be served without a frontend build.
+pub async fn spa_router(dist_dir: PathBuf) -> Router {
+    let api_routes = Router::new()
+        .route("/api", any(get_unknown_api_request))
+        .route("/api/*path", any(get_unknown_api_request));
+
+    let index = match IndexDocument::load(&dist_dir).await {
+        Ok(index) => Arc::new(index),
+        Err(err) => {
+            tracing::warn!(%err, "The frontend is not available, only the API is served");
+
+            return api_routes.fallback(get_unavailable_frontend_request);
+        }
+    };
+
+    let assets = ServeDir::new(&dist_dir)
+        .precompressed_br()
+        .precompressed_gzip()
+        .append_index_html_on_directories(false)
+        .fallback(get(get_index_request).with_state(index.clone()));
+
+    Router::new()
+        .route("/", get(get_index_request))
+        .route("/index.html", get(get_index_request))
+        .with_state(index)
+        .fallback(get_asset_request)
+        .with_state(assets)
+        .merge(api_routes)
+}
+
+/// This function will return ```StatusCode::NOT_FOUND``` for every path under `/api` which isnt an API route.
+async fn get_unknown_api_request() -> (StatusCode, &'static str) {
+    (StatusCode::NOT_FOUND, "Unknown API endpoint")
+}
+
+/// This function will return ```StatusCode::SERVICE_UNAVAILABLE``` for every page when the frontend has not been built.
+async fn get_unavailable_frontend_request() -> (StatusCode, &'static str) {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        "The frontend is not available",
+    )
+}
+
+/// This function will return `index.html` with its `Content-Security-Policy`
+/// It can either return ```StatusCode::OK```: With the document
+/// Or return ```StatusCode::NOT_MODIFIED```: When the browser's cached copy is still the current one
+async fn get_index_request(
+    State(index): State<Arc<IndexDocument>>,
+    request_headers: HeaderMap,
+) -> Response {
+    let validator_headers = index.validator_headers();
+
+    if is_not_modified(&request_headers, &index.etag, index.last_modified) {
+        return (StatusCode::NOT_MODIFIED, validator_headers).into_response();
+    }
+
+    (
+        validator_headers,
+        [(
+            CONTENT_SECURITY_POLICY,
+            index.content_security_policy.clone(),
+        )],
+        Html(index.html.clone()),
+    )
+        .into_response()
+}
+
+/// This function will return the file of the dist directory at the path of the request, or `index.html` if there is no such file.
+/// Files with a content hash in their name are cached for a year, every other file has to be revalidated with its `ETag` or `Last-Modified` date.
+async fn get_asset_request(
+    State(assets): State<ServeDir<MethodRouter>>,
+    request: Request,
+) -> Response {
+    let is_hashed = is_hashed_asset(request.uri().path());
+    let if_none_match = request.headers().get(IF_NONE_MATCH).cloned();
+
+    let mut response = assets
+        .oneshot(request)
+        .await
+        .unwrap_or_else(|err| match err {})
+        .into_response();
+
+    // The response of the fallback already has the headers of index.html
+    if response.headers().contains_key(CACHE_CONTROL) {
+        return response;
+    }
+
+    let status = response.status();
+
+    if !matches!(
+        status,
+        StatusCode::OK | StatusCode::PARTIAL_CONTENT | StatusCode::NOT_MODIFIED
+    ) {
+        return response;
+    }
+
+    let cache_control = if is_hashed {
+        IMMUTABLE_CACHE_CONTROL
+    } else {
+        REVALIDATE_CACHE_CONTROL
+    };
+
+    let mut headers = HeaderMap::new();
+
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static(cache_control));
+    // The body depends on the encodings the browser accepts, as the precompressed variants are preferred
+    headers.insert(VARY, HeaderValue::from_static(ACCEPT_ENCODING.as_str()));
+
+    if status == StatusCode::OK {
+        if let Some(etag) = asset_etag(response.headers()) {
+            headers.insert(ETAG, etag.clone());
+
+            if if_none_match.is_some_and(|if_none_match| etag_matches(&if_none_match, &etag)) {
+                if let Some(last_modified) = response.headers().get(LAST_MODIFIED) {
+                    headers.insert(LAST_MODIFIED, last_modified.clone());
+                }
+
+                return (StatusCode::NOT_MODIFIED, headers).into_response();
+            }
+        }
+    }
+
+    response.headers_mut().extend(headers);
+
+    response
+}
+
+/// This function returns whether the file name has a content hash in it, like the ones Trunk builds (e.g. `frontend-1a2b3c4d5e6f7a8b_bg.wasm`).
+fn is_hashed_asset(path: &str) -> bool {
+    let file_name = path.rsplit('/').next().unwrap_or_default();
+    let stem = file_name.split('.').next().unwrap_or_default();
+    let stem = stem.strip_suffix("_bg").unwrap_or(stem);
+
+    match stem.rsplit_once('-') {
+        Some((_, hash)) => hash.len() >= 8 && hash.chars().all(|c| c.is_ascii_hexdigit()),
+        None => false,
+    }
+}
+
+/// This function builds a weak `ETag` for a file served by ```ServeDir``` from its size and modification date, so the file doesnt have to be hashed on every request.
+/// The precompressed variants have different sizes, so they get different tags.
+fn asset_etag(response_headers: &HeaderMap) -> Option<HeaderValue> {
+    let content_length = response_headers.get(CONTENT_LENGTH)?.to_str().ok()?;
+    let last_modified = parse_http_date(response_headers.get(LAST_MODIFIED)?)?;
+
+    HeaderValue::from_str(&format!(
+        "W/\"{content_length}-{:x}\"",
+        last_modified.timestamp()
+    ))
+    .ok()
+}
+
+/// This function returns whether the browser's cached copy is still the current one, based on the conditional headers of the request.
+/// `If-Modified-Since` is only checked if there is no `If-None-Match` header, as the tag is more precise.
+fn is_not_modified(
+    request_headers: &HeaderMap,
+    etag: &HeaderValue,
+    last_modified: Option<DateTime<Utc>>,
+) -> bool {
+    if let Some(if_none_match) = request_headers.get(IF_NONE_MATCH) {
+        return etag_matches(if_none_match, etag);
+    }
+
+    match (
+        request_headers
+            .get(IF_MODIFIED_SINCE)
+            .and_then(parse_http_date),
+        last_modified,
+    ) {
+        (Some(if_modified_since), Some(last_modified)) => last_modified <= if_modified_since,
+        _ => false,
+    }
+}
+
+/// This function drops the fractional seconds of the time, as HTTP dates only have a precision of one second.
+fn truncate_to_seconds(time: SystemTime) -> DateTime<Utc> {
+    let time = DateTime::<Utc>::from(time);
+
+    DateTime::from_timestamp(time.timestamp(), 0).unwrap_or(time)
+}
+
+/// This function formats the date the way it is sent in the `Last-Modified` header.
+fn format_http_date(date: DateTime<Utc>) -> String {
+    date.format(HTTP_DATE_FORMAT).to_string()
+}
+
+/// This function parses a date sent in the `Last-Modified` or `If-Modified-Since` header.
+fn parse_http_date(date: &HeaderValue) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(date.to_str().ok()?)
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, http::HeaderName};
+
+    use super::*;
+
+    #[test]
+    fn trunk_assets_are_hashed() {
+        assert!(is_hashed_asset("/frontend-1a2b3c4d5e6f7a8b_bg.wasm"));
+        assert!(is_hashed_asset("/frontend-1a2b3c4d5e6f7a8b.js"));
+        assert!(is_hashed_asset("/styles-0123456789abcdef.css"));
+        assert!(is_hashed_asset("/assets/frontend-1A2B3C4D.js"));
+
+        assert!(!is_hashed_asset("/"));
+        assert!(!is_hashed_asset("/index.html"));
+        assert!(!is_hashed_asset("/favicon.ico"));
+        assert!(!is_hashed_asset("/public/search.svg"));
+        assert!(!is_hashed_asset("/search-bar.svg"));
+        assert!(!is_hashed_asset("/frontend-1a2b3c4_bg.wasm"));
+    }
+
+    #[test]
+    fn asset_etag_is_built_from_size_and_date() {
+        let mut headers = HeaderMap::new();
+
+        headers.insert(CONTENT_LENGTH, HeaderValue::from_static("1234"));
+        headers.insert(
+            LAST_MODIFIED,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+
+        assert_eq!(
+            asset_etag(&headers),
+            Some(HeaderValue::from_static("W/\"1234-56273e80\""))
+        );
+
+        headers.insert(CONTENT_LENGTH, HeaderValue::from_static("99"));
+
+        assert_eq!(
+            asset_etag(&headers),
+            Some(HeaderValue::from_static("W/\"99-56273e80\""))
+        );
+
+        headers.remove(LAST_MODIFIED);
+
+        assert_eq!(asset_etag(&headers), None);
+    }
+
+    #[test]
+    fn conditional_headers_are_checked() {
+        let etag = HeaderValue::from_static("\"abc\"");
+        let last_modified =
+            parse_http_date(&HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"));
+
+        let headers = |pairs: &[(HeaderName, &'static str)]| {
+            let mut headers = HeaderMap::new();
+
+            for (name, value) in pairs {
+                headers.insert(name, HeaderValue::from_static(value));
+            }
+
+            headers
+        };
+
+        assert!(!is_not_modified(&headers(&[]), &etag, last_modified));
+        assert!(is_not_modified(
+            &headers(&[(IF_NONE_MATCH, "W/\"abc\"")]),
+            &etag,
+            last_modified
+        ));
+        assert!(is_not_modified(
+            &headers(&[(IF_MODIFIED_SINCE, "Wed, 21 Oct 2015 07:28:00 GMT")]),
+            &etag,
+            last_modified
+        ));
+        assert!(!is_not_modified(
+            &headers(&[(IF_MODIFIED_SINCE, "Tue, 20 Oct 2015 07:28:00 GMT")]),
+            &etag,
+            last_modified
+        ));
+        assert!(!is_not_modified(
+            &headers(&[(IF_MODIFIED_SINCE, "Wed, 21 Oct 2015 07:28:00 GMT")]),
+            &etag,
+            None
+        ));
+        // The tag takes precedence over the date
+        assert!(!is_not_modified(
+            &headers(&[
+                (IF_NONE_MATCH, "\"xyz\""),
+                (IF_MODIFIED_SINCE, "Wed, 21 Oct 2015 07:28:00 GMT")
+            ]),
+            &etag,
+            last_modified
+        ));
+    }
+
+    #[tokio::test]
+    async fn missing_frontend_is_unavailable() {
+        let app = spa_router(PathBuf::from("/nonexistent/frontend/dist")).await;
+
+        let page = app
+            .clone()
+            .oneshot(Request::get("/search").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(page.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let api = app
+            .oneshot(Request::get("/api/unknown").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(api.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,233 @@
+//! This mod contains the logging and metrics setup of the backend, every log line is emitted through `tracing` and every metric through `metrics`.
+//! Secrets (passwords, password hashes and session cookies) must never be logged, the types containing them redact them in their ```Debug``` implementations.
+//! The metrics are exposed in the Prometheus text format at `/metrics` on a separate admin port, see ```metrics_address```.
+
+use std::{net::SocketAddr, time::Instant};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{HeaderName, Method},
+    middleware::Next,
+    response::Response,
+    routing::get,
+    Router,
+};
+use metrics::{describe_counter, describe_gauge, describe_histogram, Unit};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use tracing::Span;
+use tracing_subscriber::{fmt, EnvFilter};
+
+use crate::PgPool;
+
+/// The header the ID of every request is stored in, it is generated by the server if the client didnt send one.
+/// The ID is sent back in the response, so that a client can refer to the request when reporting an error.
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// The filter used if the `RUST_LOG` environment variable is not set.
+const DEFAULT_LOG_FILTER: &str = "info";
+
+/// The address the metrics are served on if the `METRICS_ADDRESS` environment variable is not set.
+/// It is only reachable from the host by default, as the metrics should not be public.
+const DEFAULT_METRICS_ADDRESS: &str = "[::1]:9464";
+
+/// The upper bounds of the histogram buckets in seconds, these cover everything from a cached lookup to a slow `Argon2` hash.
+const HISTOGRAM_BUCKETS: &[f64] = &[
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// The label used for requests which didnt match any route, so that unknown paths can not blow up the number of series.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// The label used for requests with a nonstandard method, as clients can send any token as the method.
+const OTHER_METHOD: &str = "other";
+
+/// The format the log lines are written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human readable, multi-line output, meant for development
+    #[default]
+    Pretty,
+    /// One JSON object per line, meant to be consumed by a log collector
+    Json,
+}
+
+impl LogFormat {
+    /// This function reads the log format from the `LOG_FORMAT` environment variable, which can either be `pretty` or `json`.
+    /// If the variable is not set ```LogFormat::Pretty``` is returned.
+    pub fn from_env() -> anyhow::Result<Self> {
+        match std::env::var("LOG_FORMAT") {
+            Ok(format) => match format.to_ascii_lowercase().as_str() {
+                "pretty" => Ok(LogFormat::Pretty),
+                "json" => Ok(LogFormat::Json),
+                _ => anyhow::bail!("Unrecognized LOG_FORMAT: {format}"),
+            },
+            Err(std::env::VarError::NotPresent) => Ok(LogFormat::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// This function installs the global `tracing` subscriber, it should be called once when the server starts.
+/// The format of the logs is read via ```LogFormat::from_env```, the verbosity can be set with the `RUST_LOG` environment variable (e.g. `RUST_LOG=backend=debug,tower_http=debug`).
+pub fn init_tracing() -> anyhow::Result<()> {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER));
+
+    let subscriber = fmt().with_env_filter(filter);
+
+    match LogFormat::from_env()? {
+        LogFormat::Pretty => subscriber.pretty().try_init(),
+        LogFormat::Json => subscriber.json().flatten_event(true).try_init(),
+    }
+    .map_err(|err| anyhow::anyhow!(err))
+}
+
+/// This function creates the span every request is handled in.
+/// Only the method, the path and the ID of the request is recorded, headers and query strings are left out as they can contain session cookies and search terms.
+pub fn make_request_span(request: &Request) -> Span {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|request_id| request_id.to_str().ok())
+        .unwrap_or_default();
+
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        path = %request.uri().path(),
+        request_id = %request_id,
+    )
+}
+
+/// This function installs the global `metrics` recorder, it should be called once when the server starts.
+/// The returned ```PrometheusHandle``` renders the current value of every metric, it should be passed to ```metrics_router```.
+pub fn install_metrics_recorder() -> anyhow::Result<PrometheusHandle> {
+    let handle = PrometheusBuilder::new()
+        .set_buckets(HISTOGRAM_BUCKETS)?
+        .install_recorder()?;
+
+    describe_counter!(
+        "http_requests_total",
+        "The number of HTTP requests handled, by route, method and status"
+    );
+    describe_histogram!(
+        "http_request_duration_seconds",
+        Unit::Seconds,
+        "The time it took to handle an HTTP request, by route, method and status"
+    );
+    describe_gauge!(
+        "db_pool_connections",
+        "The number of connections in the database pool"
+    );
+    describe_gauge!(
+        "db_pool_idle_connections",
+        "The number of idle connections in the database pool"
+    );
+    describe_gauge!(
+        "db_pool_max_connections",
+        "The maximum number of connections the database pool can open"
+    );
+    describe_counter!("logins_total", "The number of login attempts, by outcome");
+    describe_histogram!(
+        "password_hash_duration_seconds",
+        Unit::Seconds,
+        "The time it took to hash or verify a password with Argon2, by operation"
+    );
+    describe_gauge!(
+        "websocket_connections",
+        "The number of open notification WebSockets"
+    );
+    describe_counter!(
+        "jobs_total",
+        "The number of background job attempts, by kind and outcome (succeeded, retried or dead)"
+    );
+
+    Ok(handle)
+}
+
+/// This function reads the address the metrics are served on from the `METRICS_ADDRESS` environment variable.
+/// If the variable is not set ```DEFAULT_METRICS_ADDRESS``` is returned.
+pub fn metrics_address() -> anyhow::Result<SocketAddr> {
+    match std::env::var("METRICS_ADDRESS") {
+        Ok(address) => Ok(address.parse()?),
+        Err(std::env::VarError::NotPresent) => Ok(DEFAULT_METRICS_ADDRESS.parse()?),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// This function creates the router of the admin port, which serves the metrics at `/metrics`.
+pub fn metrics_router(handle: PrometheusHandle, pgconnection: PgPool) -> Router {
+    Router::new()
+        .route("/metrics", get(get_metrics_request))
+        .with_state((handle, pgconnection))
+}
+
+/// This function will return every metric in the Prometheus text format.
+/// The state of the database pool is sampled when the metrics are requested.
+async fn get_metrics_request(
+    State((handle, pgconnection)): State<(PrometheusHandle, PgPool)>,
+) -> String {
+    let pool_state = pgconnection.state();
+
+    metrics::gauge!("db_pool_connections").set(pool_state.connections);
+    metrics::gauge!("db_pool_idle_connections").set(pool_state.idle_connections);
+    metrics::gauge!("db_pool_max_connections").set(pgconnection.max_size());
+
+    handle.render()
+}
+
+/// This function returns the label of the method, every method which is not a standard one used by the API is labeled as ```OTHER_METHOD```.
+fn method_label(method: &Method) -> &'static str {
+    match *method {
+        Method::GET => "GET",
+        Method::HEAD => "HEAD",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::DELETE => "DELETE",
+        Method::PATCH => "PATCH",
+        Method::OPTIONS => "OPTIONS",
+        _ => OTHER_METHOD,
+    }
+}
+
+/// This function records the count and the latency of every request, labeled with the matched route instead of the raw path.
+/// It should be added with ```Router::layer```, so that the matched route is known when it runs.
+pub async fn track_http_metrics(request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|matched_path| matched_path.as_str().to_string())
+        .unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+    let method = method_label(request.method()).to_string();
+
+    let start = Instant::now();
+
+    let response = next.run(request).await;
+
+    let labels = [
+        ("route", route),
+        ("method", method),
+        ("status", response.status().as_u16().to_string()),
+    ];
+
+    metrics::counter!("http_requests_total", &labels).increment(1);
+    metrics::histogram!("http_request_duration_seconds", &labels).record(start.elapsed());
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nonstandard_methods_share_one_label() {
+        assert_eq!(method_label(&Method::GET), "GET");
+        assert_eq!(method_label(&Method::DELETE), "DELETE");
+        assert_eq!(method_label(&Method::TRACE), OTHER_METHOD);
+        assert_eq!(
+            method_label(&Method::from_bytes(b"RANDOM-12345").unwrap()),
+            OTHER_METHOD
+        );
+    }
+}
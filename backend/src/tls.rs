@@ -0,0 +1,172 @@
+//! This mod contains the optional TLS termination of the server.
+//! TLS is enabled by setting both the `TLS_CERT_PATH` and `TLS_KEY_PATH` environment variables to PEM files, the certificates are reloaded whenever these files change.
+//! When TLS is enabled, plain HTTP requests are redirected to HTTPS by a separate listener and every response carries the `Strict-Transport-Security` header.
+
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+
+use axum::{
+    extract::Request,
+    http::{header::HOST, HeaderValue},
+    response::Redirect,
+    Router,
+};
+use axum_server::tls_rustls::RustlsConfig;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tower_http::set_header::SetResponseHeaderLayer;
+
+/// The address the HTTP to HTTPS redirect is served on if the `HTTP_REDIRECT_ADDRESS` environment variable is not set.
+const DEFAULT_HTTP_REDIRECT_ADDRESS: &str = "[::]:3080";
+
+/// The value of the `Strict-Transport-Security` header, browsers will only connect via HTTPS for a year after seeing it.
+const HSTS_HEADER_VALUE: &str = "max-age=31536000; includeSubDomains";
+
+/// The paths and addresses TLS is configured with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TlsSettings {
+    /// The PEM file containing the certificate chain
+    pub cert_path: PathBuf,
+    /// The PEM file containing the private key of the certificate
+    pub key_path: PathBuf,
+    /// The address plain HTTP requests are accepted and redirected to HTTPS on
+    pub redirect_address: SocketAddr,
+}
+
+impl TlsSettings {
+    /// This function reads the TLS settings from the `TLS_CERT_PATH`, `TLS_KEY_PATH` and `HTTP_REDIRECT_ADDRESS` environment variables.
+    /// It returns ```None``` if neither path is set, in which case the server should be served over plain HTTP.
+    /// It returns an error if only one of the paths is set, or the redirect address is invalid.
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        let cert_path = std::env::var_os("TLS_CERT_PATH").map(PathBuf::from);
+        let key_path = std::env::var_os("TLS_KEY_PATH").map(PathBuf::from);
+
+        let (cert_path, key_path) = match (cert_path, key_path) {
+            (Some(cert_path), Some(key_path)) => (cert_path, key_path),
+            (None, None) => return Ok(None),
+            _ => anyhow::bail!("TLS_CERT_PATH and TLS_KEY_PATH have to be set together"),
+        };
+
+        let redirect_address = match std::env::var("HTTP_REDIRECT_ADDRESS") {
+            Ok(address) => address.parse()?,
+            Err(std::env::VarError::NotPresent) => DEFAULT_HTTP_REDIRECT_ADDRESS.parse()?,
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(Some(Self {
+            cert_path,
+            key_path,
+            redirect_address,
+        }))
+    }
+
+    /// This function loads the certificate and the key into a new ```RustlsConfig```.
+    pub async fn load_config(&self) -> anyhow::Result<RustlsConfig> {
+        Ok(RustlsConfig::from_pem_file(&self.cert_path, &self.key_path).await?)
+    }
+
+    /// This function reloads the certificate and the key into the ```RustlsConfig``` whenever either file changes, new connections use the reloaded certificate.
+    /// The directories of the files are watched instead of the files themselves, so that files replaced by renaming (e.g. by certbot) are picked up too.
+    /// The returned watcher has to be kept alive for as long as the certificates should be reloaded.
+    pub fn watch_certificates(&self, config: RustlsConfig) -> anyhow::Result<RecommendedWatcher> {
+        let (change_sender, mut change_receiver) = mpsc::channel::<()>(1);
+
+        let watched_paths = [self.cert_path.clone(), self.key_path.clone()];
+
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let Ok(event) = event else {
+                    return;
+                };
+
+                if event.kind.is_access() {
+                    return;
+                }
+
+                if event.paths.iter().any(|path| {
+                    watched_paths
+                        .iter()
+                        .any(|watched_path| path.ends_with(watched_path))
+                }) {
+                    // A reload is already pending if the channel is full, so the change can be dropped
+                    let _ = change_sender.try_send(());
+                }
+            })?;
+
+        for directory in [
+            watched_directory(&self.cert_path),
+            watched_directory(&self.key_path),
+        ] {
+            watcher.watch(directory, RecursiveMode::NonRecursive)?;
+        }
+
+        let settings = self.clone();
+
+        tokio::spawn(async move {
+            while change_receiver.recv().await.is_some() {
+                match config
+                    .reload_from_pem_file(&settings.cert_path, &settings.key_path)
+                    .await
+                {
+                    Ok(()) => tracing::info!("Reloaded the TLS certificate"),
+                    // The files can be half-written while they are being replaced, the next change triggers another reload
+                    Err(err) => tracing::warn!(%err, "Failed to reload the TLS certificate"),
+                }
+            }
+        });
+
+        Ok(watcher)
+    }
+}
+
+/// This function returns the directory a file is in, which is the current directory for bare file names.
+fn watched_directory(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// This function creates the router of the HTTP listener, which redirects every request to the same path over HTTPS.
+/// The port of the `Host` header is replaced with ```https_port```, which is left out if it is the default 443.
+pub fn redirect_router(https_port: u16) -> Router {
+    Router::new().fallback(move |request: Request| async move {
+        let host = request
+            .headers()
+            .get(HOST)
+            .and_then(|host| host.to_str().ok())
+            .unwrap_or("localhost");
+
+        Redirect::permanent(&https_url(host, https_port, request.uri()))
+    })
+}
+
+/// This function builds the HTTPS URL of the request made to the host and uri.
+fn https_url(host: &str, https_port: u16, uri: &axum::http::Uri) -> String {
+    // The port is cut off after the last colon, unless the colon is part of an IPv6 address
+    let hostname = match host.rsplit_once(':') {
+        Some((hostname, port)) if !port.contains(']') => hostname,
+        _ => host,
+    };
+
+    let path_and_query = uri
+        .path_and_query()
+        .map(|path| path.as_str())
+        .unwrap_or("/");
+
+    if https_port == 443 {
+        format!("https://{hostname}{path_and_query}")
+    } else {
+        format!("https://{hostname}:{https_port}{path_and_query}")
+    }
+}
+
+/// This function creates the layer which adds the `Strict-Transport-Security` header to every response, it should only be used when TLS is enabled.
+pub fn hsts_layer() -> SetResponseHeaderLayer<HeaderValue> {
+    SetResponseHeaderLayer::if_not_present(
+        axum::http::header::STRICT_TRANSPORT_SECURITY,
+        HeaderValue::from_static(HSTS_HEADER_VALUE),
+    )
+}
@@ -0,0 +1,516 @@
+//! These tests need a `PostgreSQL` database, its URL is read from the `TEST_DATABASE_URL` environment variable.
+//! They are ignored by default, run them with `cargo test --test accounts -- --ignored`, the migrations are run on the database before the tests.
+
+use std::sync::{Barrier, Once};
+
+use axum::{
+    body::Body,
+    http::{
+        header::{CONTENT_TYPE, COOKIE, USER_AGENT},
+        Request, StatusCode,
+    },
+    routing::{get, post},
+    Router,
+};
+use backend::{
+    cache::ResponseCache,
+    db_types::{
+        safe_types::{
+            AccountActionRequest, ModerationAction, ModerationRequest, ReportReason, ReportRequest,
+            TargetKind,
+        },
+        unsafe_types::AuthorizedUser,
+    },
+    get_admin_account_suspend_request, get_admin_session_revoke_request,
+    get_cookie_account_request, get_moderation_action_request, get_report_request,
+    get_v1_account_request,
+    lifecycle::MIGRATIONS,
+    safe_functions::create_report,
+    schema::*,
+    ServerState, REPORT_HIDE_THRESHOLD,
+};
+use diesel::{dsl::IntervalDsl, prelude::*, r2d2::ConnectionManager, PgConnection};
+use diesel_migrations::MigrationHarness;
+use tokio_util::sync::CancellationToken;
+use tower::util::ServiceExt;
+
+/// The migrations are only run by the first test, as the tests run in parallel.
+static MIGRATE: Once = Once::new();
+
+/// This function creates a ```ServerState``` connected to the test database.
+/// It panics if `TEST_DATABASE_URL` is not set, so that the tests can not pass without checking anything.
+fn test_database_state() -> ServerState {
+    let database_url = std::env::var("TEST_DATABASE_URL")
+        .expect("TEST_DATABASE_URL should be set to run these tests");
+
+    let pool = r2d2::Builder::new()
+        .max_size(4)
+        .build(ConnectionManager::<PgConnection>::new(database_url))
+        .unwrap();
+
+    MIGRATE.call_once(|| {
+        pool.get()
+            .unwrap()
+            .run_pending_migrations(MIGRATIONS)
+            .unwrap();
+    });
+
+    let (notification_sender, _) = tokio::sync::broadcast::channel(16);
+
+    ServerState {
+        pgconnection: pool,
+        notification_sender,
+        shutdown: CancellationToken::new(),
+        response_cache: ResponseCache::new(),
+    }
+}
+
+/// This function creates an account with a random username, and returns its ID.
+fn create_test_account(state: &ServerState) -> i32 {
+    diesel::insert_into(accounts::table)
+        .values((
+            accounts::username.eq(format!("test-{}", uuid::Uuid::now_v7())),
+            accounts::passw.eq(""),
+        ))
+        .returning(accounts::id)
+        .get_result(&mut state.pgconnection.get().unwrap())
+        .unwrap()
+}
+
+/// This function creates a session for the account, and returns the `Cookie` header logging in with it.
+fn create_test_session(state: &ServerState, account_id: i32) -> String {
+    let session = AuthorizedUser {
+        client_signature: "test".to_string(),
+        session_id: uuid::Uuid::now_v7().to_string(),
+        account_id,
+    };
+
+    diesel::insert_into(authorized_users::table)
+        .values((
+            authorized_users::client_signature.eq(&session.client_signature),
+            authorized_users::session_id.eq(&session.session_id),
+            authorized_users::account_id.eq(session.account_id),
+        ))
+        .execute(&mut state.pgconnection.get().unwrap())
+        .unwrap();
+
+    format!("session_id={}", serde_json::to_string(&session).unwrap())
+}
+
+/// This function sends a `GET` request to `/api/v1/me` with the cookie, and returns the status of the response.
+async fn get_me_status(state: &ServerState, cookie: &str) -> StatusCode {
+    let app = Router::new()
+        .route("/api/v1/me", get(get_cookie_account_request))
+        .with_state(state.clone());
+
+    app.oneshot(
+        Request::get("/api/v1/me")
+            .header(COOKIE, cookie)
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await
+    .unwrap()
+    .status()
+}
+
+/// This function sends a `GET` request to `/api/v1/me` with the cookie from a client with a unique `User-Agent`, and returns the number of session rejections recorded for the client.
+async fn count_recorded_rejections(state: &ServerState, cookie: &str) -> i64 {
+    let app = Router::new()
+        .route("/api/v1/me", get(get_cookie_account_request))
+        .with_state(state.clone());
+
+    let user_agent = format!("test-{}", uuid::Uuid::now_v7());
+
+    let status = app
+        .oneshot(
+            Request::get("/api/v1/me")
+                .header(COOKIE, cookie)
+                .header(USER_AGENT, &user_agent)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status();
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    audit_events::table
+        .filter(audit_events::user_agent.eq(&user_agent))
+        .count()
+        .get_result(&mut state.pgconnection.get().unwrap())
+        .unwrap()
+}
+
+/// This function makes the account specified in the ```blocker_id``` argument block the one specified in the ```blocked_id``` argument.
+fn create_test_block(state: &ServerState, blocker_id: i32, blocked_id: i32) {
+    diesel::insert_into(account_blocks::table)
+        .values((
+            account_blocks::blocker_id.eq(blocker_id),
+            account_blocks::blocked_id.eq(blocked_id),
+        ))
+        .execute(&mut state.pgconnection.get().unwrap())
+        .unwrap();
+}
+
+/// This function sends a `GET` request for the profile of the account with the cookie, and returns the status of the response.
+async fn get_profile_status(state: &ServerState, account_id: i32, cookie: &str) -> StatusCode {
+    let app = Router::new()
+        .route("/api/v1/accounts/:id", get(get_v1_account_request))
+        .with_state(state.clone());
+
+    app.oneshot(
+        Request::get(format!("/api/v1/accounts/{account_id}"))
+            .header(COOKIE, cookie)
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await
+    .unwrap()
+    .status()
+}
+
+/// This function reports the account with the cookie, and returns the status of the response.
+async fn post_report_status(state: &ServerState, account_id: i32, cookie: &str) -> StatusCode {
+    let app = Router::new()
+        .route("/api/v1/reports", post(get_report_request))
+        .with_state(state.clone());
+
+    let report = ReportRequest {
+        target_kind: TargetKind::Account,
+        target_id: account_id,
+        reason: ReportReason::Spam,
+        details: None,
+    };
+
+    app.oneshot(
+        Request::post("/api/v1/reports")
+            .header(COOKIE, cookie)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::to_vec(&report).unwrap()))
+            .unwrap(),
+    )
+    .await
+    .unwrap()
+    .status()
+}
+
+/// This function grants the role with the name passed in to the account.
+fn grant_test_role(state: &ServerState, account_id: i32, role_name: &str) {
+    let role_id: i32 = roles::table
+        .filter(roles::name.eq(role_name))
+        .select(roles::id)
+        .first(&mut state.pgconnection.get().unwrap())
+        .unwrap();
+
+    diesel::insert_into(account_roles::table)
+        .values((
+            account_roles::account_id.eq(account_id),
+            account_roles::role_id.eq(role_id),
+        ))
+        .execute(&mut state.pgconnection.get().unwrap())
+        .unwrap();
+}
+
+/// This function sends an admin account action for the account with the cookie to the path, and returns the status of the response.
+async fn post_admin_account_action_status(
+    state: &ServerState,
+    path: &str,
+    account_id: i32,
+    cookie: &str,
+) -> StatusCode {
+    let app = Router::new()
+        .route(
+            "/api/admin/accounts/suspend",
+            post(get_admin_account_suspend_request),
+        )
+        .route(
+            "/api/admin/sessions/revoke",
+            post(get_admin_session_revoke_request),
+        )
+        .with_state(state.clone());
+
+    let action = AccountActionRequest {
+        account_id,
+        note: None,
+    };
+
+    app.oneshot(
+        Request::post(path)
+            .header(COOKIE, cookie)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::to_vec(&action).unwrap()))
+            .unwrap(),
+    )
+    .await
+    .unwrap()
+    .status()
+}
+
+/// This function sends the moderation action against the account with the cookie, and returns the status of the response.
+async fn post_moderation_action_status(
+    state: &ServerState,
+    action: ModerationAction,
+    account_id: i32,
+    cookie: &str,
+) -> StatusCode {
+    let app = Router::new()
+        .route(
+            "/api/moderation/actions",
+            post(get_moderation_action_request),
+        )
+        .with_state(state.clone());
+
+    let request = ModerationRequest {
+        target_kind: TargetKind::Account,
+        target_id: account_id,
+        action,
+        note: None,
+    };
+
+    app.oneshot(
+        Request::post("/api/moderation/actions")
+            .header(COOKIE, cookie)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::to_vec(&request).unwrap()))
+            .unwrap(),
+    )
+    .await
+    .unwrap()
+    .status()
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL database, its URL is read from the TEST_DATABASE_URL environment variable"]
+async fn automatically_hidden_account_can_still_authenticate() {
+    let state = test_database_state();
+
+    let account_id = create_test_account(&state);
+    let cookie = create_test_session(&state, account_id);
+
+    // The same state the reports leave the account in
+    diesel::update(accounts::table.find(account_id))
+        .set((
+            accounts::hidden_at.eq(diesel::dsl::now.nullable()),
+            accounts::hidden_until.eq((diesel::dsl::now + 72.hours()).nullable()),
+        ))
+        .execute(&mut state.pgconnection.get().unwrap())
+        .unwrap();
+
+    assert_eq!(get_me_status(&state, &cookie).await, StatusCode::OK);
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL database, its URL is read from the TEST_DATABASE_URL environment variable"]
+async fn suspended_account_is_rejected() {
+    let state = test_database_state();
+
+    let account_id = create_test_account(&state);
+    let cookie = create_test_session(&state, account_id);
+
+    diesel::update(accounts::table.find(account_id))
+        .set(accounts::hidden_at.eq(diesel::dsl::now.nullable()))
+        .execute(&mut state.pgconnection.get().unwrap())
+        .unwrap();
+
+    assert_eq!(
+        get_me_status(&state, &cookie).await,
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL database, its URL is read from the TEST_DATABASE_URL environment variable"]
+async fn only_rejected_sessions_of_existing_accounts_are_recorded() {
+    let state = test_database_state();
+
+    let account_id = create_test_account(&state);
+
+    let forged_session = |account_id: i32| {
+        let session = AuthorizedUser {
+            client_signature: "test".to_string(),
+            session_id: uuid::Uuid::now_v7().to_string(),
+            account_id,
+        };
+
+        format!("session_id={}", serde_json::to_string(&session).unwrap())
+    };
+
+    assert_eq!(
+        count_recorded_rejections(&state, "session_id=garbage").await,
+        0
+    );
+    assert_eq!(
+        count_recorded_rejections(&state, &forged_session(-1)).await,
+        0
+    );
+    assert_eq!(
+        count_recorded_rejections(&state, &forged_session(account_id)).await,
+        1
+    );
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL database, its URL is read from the TEST_DATABASE_URL environment variable"]
+async fn blocked_account_can_not_see_the_blockers_profile() {
+    let state = test_database_state();
+
+    let blocker_id = create_test_account(&state);
+    let blocked_id = create_test_account(&state);
+    let other_id = create_test_account(&state);
+
+    create_test_block(&state, blocker_id, blocked_id);
+
+    let blocked_cookie = create_test_session(&state, blocked_id);
+    let other_cookie = create_test_session(&state, other_id);
+
+    assert_eq!(
+        get_profile_status(&state, blocker_id, &blocked_cookie).await,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        get_profile_status(&state, blocker_id, &other_cookie).await,
+        StatusCode::OK
+    );
+    // The block only works in one direction
+    assert_eq!(
+        get_profile_status(&state, blocked_id, &create_test_session(&state, blocker_id)).await,
+        StatusCode::OK
+    );
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL database, its URL is read from the TEST_DATABASE_URL environment variable"]
+async fn blocked_account_can_not_report_the_blocker() {
+    let state = test_database_state();
+
+    let blocker_id = create_test_account(&state);
+    let blocked_id = create_test_account(&state);
+    let other_id = create_test_account(&state);
+
+    create_test_block(&state, blocker_id, blocked_id);
+
+    assert_eq!(
+        post_report_status(&state, blocker_id, &create_test_session(&state, blocked_id)).await,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        post_report_status(&state, blocker_id, &create_test_session(&state, other_id)).await,
+        StatusCode::CREATED
+    );
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL database, its URL is read from the TEST_DATABASE_URL environment variable"]
+async fn concurrent_reports_hide_the_target() {
+    let state = test_database_state();
+
+    let target_id = create_test_account(&state);
+    let reporter_ids: Vec<i32> = (0..REPORT_HIDE_THRESHOLD)
+        .map(|_| create_test_account(&state))
+        .collect();
+
+    // Every report is submitted at the same time, so each of them would count the others as missing without the lock
+    let barrier = Barrier::new(reporter_ids.len());
+
+    std::thread::scope(|scope| {
+        for &reporter_id in &reporter_ids {
+            let (barrier, pgconnection) = (&barrier, state.pgconnection.clone());
+
+            scope.spawn(move || {
+                let report = ReportRequest {
+                    target_kind: TargetKind::Account,
+                    target_id,
+                    reason: ReportReason::Spam,
+                    details: None,
+                };
+
+                barrier.wait();
+
+                create_report(reporter_id, report, pgconnection).unwrap();
+            });
+        }
+    });
+
+    let hidden_at: Option<chrono::NaiveDateTime> = accounts::table
+        .find(target_id)
+        .select(accounts::hidden_at)
+        .first(&mut state.pgconnection.get().unwrap())
+        .unwrap();
+
+    assert!(hidden_at.is_some());
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL database, its URL is read from the TEST_DATABASE_URL environment variable"]
+async fn admins_can_not_lock_out_account_managers() {
+    let state = test_database_state();
+
+    let admin_id = create_test_account(&state);
+    let other_admin_id = create_test_account(&state);
+    let user_id = create_test_account(&state);
+
+    grant_test_role(&state, admin_id, "admin");
+    grant_test_role(&state, other_admin_id, "admin");
+
+    let cookie = create_test_session(&state, admin_id);
+
+    for path in ["/api/admin/accounts/suspend", "/api/admin/sessions/revoke"] {
+        assert_eq!(
+            post_admin_account_action_status(&state, path, admin_id, &cookie).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            post_admin_account_action_status(&state, path, other_admin_id, &cookie).await,
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    assert_eq!(
+        post_admin_account_action_status(&state, "/api/admin/sessions/revoke", user_id, &cookie)
+            .await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        post_admin_account_action_status(&state, "/api/admin/accounts/suspend", user_id, &cookie)
+            .await,
+        StatusCode::OK
+    );
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL database, its URL is read from the TEST_DATABASE_URL environment variable"]
+async fn moderators_can_not_lock_out_admins() {
+    let state = test_database_state();
+
+    let moderator_id = create_test_account(&state);
+    let admin_id = create_test_account(&state);
+    let user_id = create_test_account(&state);
+
+    grant_test_role(&state, moderator_id, "moderator");
+    grant_test_role(&state, admin_id, "admin");
+
+    let cookie = create_test_session(&state, moderator_id);
+    let admin_cookie = create_test_session(&state, admin_id);
+
+    for account_id in [admin_id, moderator_id] {
+        assert_eq!(
+            post_moderation_action_status(&state, ModerationAction::Ban, account_id, &cookie).await,
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    // Revoking sessions is left to the admins
+    assert_eq!(
+        post_moderation_action_status(&state, ModerationAction::RevokeSessions, user_id, &cookie)
+            .await,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(get_me_status(&state, &admin_cookie).await, StatusCode::OK);
+
+    assert_eq!(
+        post_moderation_action_status(&state, ModerationAction::Ban, user_id, &cookie).await,
+        StatusCode::OK
+    );
+}
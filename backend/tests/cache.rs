@@ -0,0 +1,121 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_NONE_MATCH},
+        Request, StatusCode,
+    },
+    middleware,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use backend::cache::{not_modified, CachedJson};
+use tower::util::ServiceExt;
+
+/// This function returns the response of the cached route, so that the tests can read its `ETag`.
+fn cached_response() -> CachedJson {
+    CachedJson::new(&vec!["Elektronika", "Jármű"]).unwrap()
+}
+
+/// This function creates a router with the `304 Not Modified` middleware in front of it.
+fn app() -> Router {
+    Router::new()
+        .route(
+            "/cached",
+            get(|| async { cached_response() }).post(|| async { cached_response() }),
+        )
+        .route(
+            "/missing",
+            get(|| async { (StatusCode::NOT_FOUND, cached_response()).into_response() }),
+        )
+        .layer(middleware::from_fn(not_modified))
+}
+
+/// This function sends the request to the app.
+async fn send(request: Request<Body>) -> Response {
+    app().oneshot(request).await.unwrap()
+}
+
+/// This function returns the `ETag` the cached route responds with.
+async fn current_etag() -> String {
+    let response = send(Request::get("/cached").body(Body::empty()).unwrap()).await;
+
+    response.headers()[ETAG].to_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn cached_response_has_validators() {
+    let response = send(Request::get("/cached").body(Body::empty()).unwrap()).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+    assert_eq!(response.headers()[CACHE_CONTROL], "no-cache");
+    assert!(response.headers().contains_key(ETAG));
+}
+
+#[tokio::test]
+async fn matching_etag_gets_not_modified_without_body() {
+    let etag = current_etag().await;
+
+    let response = send(
+        Request::get("/cached")
+            .header(IF_NONE_MATCH, format!("\"stale\", {etag}"))
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()[ETAG], etag.as_str());
+    assert_eq!(response.headers()[CACHE_CONTROL], "no-cache");
+    assert!(!response.headers().contains_key(CONTENT_TYPE));
+    assert!(to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn stale_etag_gets_the_full_response() {
+    let response = send(
+        Request::get("/cached")
+            .header(IF_NONE_MATCH, "\"stale\"")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn non_get_requests_pass_through() {
+    let etag = current_etag().await;
+
+    let response = send(
+        Request::post("/cached")
+            .header(IF_NONE_MATCH, etag)
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn non_ok_responses_pass_through() {
+    let response = send(
+        Request::get("/missing")
+            .header(IF_NONE_MATCH, "*")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
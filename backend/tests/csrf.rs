@@ -0,0 +1,165 @@
+use axum::{
+    body::Body,
+    http::{
+        header::{AUTHORIZATION, COOKIE, HOST, ORIGIN, REFERER, SET_COOKIE},
+        HeaderValue, Request, StatusCode,
+    },
+    middleware,
+    response::Response,
+    routing::get,
+    Router,
+};
+use backend::csrf::{csrf_protection, CSRF_COOKIE_NAME, CSRF_HEADER};
+use tower::util::ServiceExt;
+
+/// The host the test requests are sent to
+const HOST_NAME: &str = "localhost:3000";
+
+/// The token the test clients have in their cookie
+const TOKEN: &str = "dGhlLXRva2VuLW9mLXRoZS10ZXN0LWNsaWVudA";
+
+/// This function creates a router with the CSRF protection in front of it, which only allows `https://hasznalt.hu` besides its own origin.
+fn protected_app() -> Router {
+    Router::new()
+        .route("/api/resource", get(|| async {}).post(|| async {}))
+        .layer(middleware::from_fn_with_state(
+            vec![HeaderValue::from_static("https://hasznalt.hu")],
+            csrf_protection,
+        ))
+}
+
+/// This function creates a `POST` request from a client which has the token in its cookie.
+fn post_request() -> axum::http::request::Builder {
+    Request::post("/api/resource")
+        .header(HOST, HOST_NAME)
+        .header(COOKIE, format!("{CSRF_COOKIE_NAME}={TOKEN}"))
+}
+
+/// This function sends the request to the protected app.
+async fn send(request: axum::http::request::Builder) -> Response {
+    protected_app()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn post_without_token_header_is_forbidden() {
+    let response = send(post_request().header(ORIGIN, "http://localhost:3000")).await;
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn post_with_mismatching_token_is_forbidden() {
+    let response = send(
+        post_request()
+            .header(ORIGIN, "http://localhost:3000")
+            .header(CSRF_HEADER, "dGhlLXRva2VuLW9mLWFub3RoZXItY2xpZW50LXh4"),
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn post_from_foreign_origin_is_forbidden() {
+    let response = send(
+        post_request()
+            .header(ORIGIN, "https://attacker.example")
+            .header(CSRF_HEADER, TOKEN),
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // The referer is checked when there is no origin
+    let response = send(
+        post_request()
+            .header(REFERER, "https://attacker.example/page")
+            .header(CSRF_HEADER, TOKEN),
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn post_from_null_origin_is_forbidden() {
+    let response = send(
+        post_request()
+            .header(ORIGIN, "null")
+            .header(CSRF_HEADER, TOKEN),
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn post_from_trusted_origin_with_token_passes() {
+    let response = send(
+        post_request()
+            .header(ORIGIN, "http://localhost:3000")
+            .header(CSRF_HEADER, TOKEN),
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = send(
+        post_request()
+            .header(ORIGIN, "https://hasznalt.hu")
+            .header(CSRF_HEADER, TOKEN),
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn authorization_header_without_cookie_is_exempt() {
+    let response = send(
+        Request::post("/api/resource")
+            .header(HOST, HOST_NAME)
+            .header(ORIGIN, "https://attacker.example")
+            .header(AUTHORIZATION, "Bearer api-token"),
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Browsers can attach both, so a request with a cookie has to pass the checks
+    let response = send(post_request().header(AUTHORIZATION, "Bearer api-token")).await;
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn first_get_issues_the_cookie() {
+    let response = send(Request::get("/api/resource").header(HOST, HOST_NAME)).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let cookie = response
+        .headers()
+        .get(SET_COOKIE)
+        .expect("the cookie should be issued")
+        .to_str()
+        .unwrap();
+
+    assert!(cookie.starts_with(&format!("{CSRF_COOKIE_NAME}=")));
+    assert!(cookie.contains("Secure"));
+    assert!(cookie.contains("SameSite=Strict"));
+    assert!(!cookie.contains("HttpOnly"));
+
+    // Clients which already have a token keep it
+    let response = send(
+        Request::get("/api/resource")
+            .header(HOST, HOST_NAME)
+            .header(COOKIE, format!("{CSRF_COOKIE_NAME}={TOKEN}")),
+    )
+    .await;
+
+    assert!(response.headers().get(SET_COOKIE).is_none());
+}
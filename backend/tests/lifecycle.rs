@@ -0,0 +1,229 @@
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    routing::get,
+    Router,
+};
+use backend::{
+    cache::ResponseCache,
+    lifecycle::{get_healthz_request, get_readyz_request, serve_with_graceful_shutdown, Readiness},
+    ServerState,
+};
+use diesel::{r2d2::ConnectionManager, PgConnection};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::oneshot,
+    time::{sleep, timeout, Instant},
+};
+use tokio_util::sync::CancellationToken;
+use tower::util::ServiceExt;
+
+/// This function creates a ```ServerState``` whose database is unreachable, nothing listens on port 1.
+fn unreachable_database_state() -> ServerState {
+    let connection_manager =
+        ConnectionManager::<PgConnection>::new("postgres://postgres@127.0.0.1:1/hasznalt");
+
+    let pool = r2d2::Builder::new()
+        .min_idle(Some(0))
+        .build_unchecked(connection_manager);
+
+    let (notification_sender, _) = tokio::sync::broadcast::channel(1);
+
+    ServerState {
+        pgconnection: pool,
+        notification_sender,
+        shutdown: CancellationToken::new(),
+        response_cache: ResponseCache::new(),
+    }
+}
+
+/// This function sends a `GET` request to the path over a fresh connection and returns the raw response.
+async fn send_raw_request(address: SocketAddr, path: &str) -> String {
+    let mut stream = TcpStream::connect(address).await.unwrap();
+
+    stream
+        .write_all(
+            format!("GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+                .as_bytes(),
+        )
+        .await
+        .unwrap();
+
+    let mut response = String::new();
+
+    stream.read_to_string(&mut response).await.unwrap();
+
+    response
+}
+
+#[tokio::test]
+async fn healthz_is_ok_without_a_database() {
+    let app = Router::new().route("/healthz", get(get_healthz_request));
+
+    let response = app
+        .oneshot(Request::get("/healthz").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn readyz_is_unavailable_when_the_database_is_unreachable() {
+    let app = Router::new()
+        .route("/readyz", get(get_readyz_request))
+        .with_state(unreachable_database_state());
+
+    let response = app
+        .oneshot(Request::get("/readyz").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    let readiness: Readiness =
+        serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+
+    assert!(!readiness.database);
+    assert!(!readiness.migrations);
+    assert!(!readiness.shutting_down);
+}
+
+#[tokio::test]
+async fn readyz_reports_a_shutdown_in_progress() {
+    let state = unreachable_database_state();
+
+    state.shutdown.cancel();
+
+    let app = Router::new()
+        .route("/readyz", get(get_readyz_request))
+        .with_state(state);
+
+    let response = app
+        .oneshot(Request::get("/readyz").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    let readiness: Readiness =
+        serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+
+    assert!(readiness.shutting_down);
+}
+
+#[test]
+fn readiness_requires_every_check() {
+    let ready = Readiness {
+        database: true,
+        migrations: true,
+        shutting_down: false,
+    };
+
+    assert!(ready.is_ready());
+    assert!(!Readiness {
+        migrations: false,
+        ..ready
+    }
+    .is_ready());
+    assert!(!Readiness {
+        database: false,
+        ..ready
+    }
+    .is_ready());
+    assert!(!Readiness {
+        shutting_down: true,
+        ..ready
+    }
+    .is_ready());
+}
+
+#[tokio::test]
+async fn shutdown_drains_in_flight_requests() {
+    let (started_sender, started_receiver) = oneshot::channel::<()>();
+    let started_sender = Arc::new(Mutex::new(Some(started_sender)));
+
+    let app = Router::new().route(
+        "/slow",
+        get(move || {
+            if let Some(started_sender) = started_sender.lock().unwrap().take() {
+                let _ = started_sender.send(());
+            }
+
+            async {
+                sleep(Duration::from_millis(200)).await;
+
+                "done"
+            }
+        }),
+    );
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let shutdown = CancellationToken::new();
+
+    let server = tokio::spawn(serve_with_graceful_shutdown(
+        listener,
+        app,
+        shutdown.clone(),
+        Duration::from_secs(5),
+    ));
+
+    let request = tokio::spawn(send_raw_request(address, "/slow"));
+
+    started_receiver.await.unwrap();
+    shutdown.cancel();
+
+    let response = request.await.unwrap();
+
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+    assert!(response.ends_with("done"), "{response}");
+
+    timeout(Duration::from_secs(5), server)
+        .await
+        .expect("the server didnt stop after the in-flight request finished")
+        .unwrap()
+        .unwrap();
+
+    // New connections are refused once the server has stopped
+    assert!(TcpStream::connect(address).await.is_err());
+}
+
+#[tokio::test]
+async fn shutdown_gives_up_after_the_timeout() {
+    let app = Router::new().route("/stuck", get(std::future::pending::<()>));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let shutdown = CancellationToken::new();
+
+    let server = tokio::spawn(serve_with_graceful_shutdown(
+        listener,
+        app,
+        shutdown.clone(),
+        Duration::from_millis(200),
+    ));
+
+    let _request = tokio::spawn(send_raw_request(address, "/stuck"));
+
+    // Give the request time to reach the handler
+    sleep(Duration::from_millis(100)).await;
+
+    let shutdown_started = Instant::now();
+    shutdown.cancel();
+
+    timeout(Duration::from_secs(5), server)
+        .await
+        .expect("the server didnt stop after the shutdown timeout")
+        .unwrap()
+        .unwrap();
+
+    assert!(shutdown_started.elapsed() >= Duration::from_millis(200));
+}
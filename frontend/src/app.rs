@@ -1,8 +1,8 @@
 use frontend::{
-    get_cookie, request_account_lookup_from_cookie, request_account_lookup_from_id, AccountCredentials, AccountLookup, AccountPageProperties, Button, TextField
+    get_cookie, request_account_lookup_from_cookie, request_account_lookup_from_id, request_account_permissions, request_admin_account_action, request_admin_account_search, request_admin_create_category, request_admin_create_category_attribute, request_admin_delete_category, request_admin_delete_category_attribute, request_admin_statistics, request_categories, request_category_attributes, request_save_search, request_unread_notification_count, subscribe_notifications, AccountCredentials, AccountLookup, AccountPageProperties, AdminAccount, AdminAccountAction, ApiClient, AutocompleteTextField, Button, Category, CategoryAttribute, NewCategoryAttribute, SavedSearchRequest, SiteStatistics, TextField
 };
-use reqwest::Client;
 use wasm_bindgen_futures::spawn_local;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
 use yew::prelude::*;
 use yew_router::{hooks::use_navigator, BrowserRouter, Routable, Switch};
 
@@ -16,6 +16,12 @@ enum Route {
     Login,
     #[at("/account/:id")]
     IdLookup { id: i32 },
+    #[at("/admin")]
+    Admin,
+    #[at("/admin/accounts")]
+    AdminAccounts,
+    #[at("/admin/categories")]
+    AdminCategories,
 }
 
 fn switch(routes: Route) -> Html {
@@ -24,6 +30,9 @@ fn switch(routes: Route) -> Html {
         Route::Register => html! { <Register /> },
         Route::Login => html! { <Login /> },
         Route::IdLookup { id } => html! { <Account id={id}/> },
+        Route::Admin => html! { <AdminPanel permission="view_statistics"><AdminStatistics /></AdminPanel> },
+        Route::AdminAccounts => html! { <AdminPanel permission="manage_accounts"><AdminAccounts /></AdminPanel> },
+        Route::AdminCategories => html! { <AdminPanel permission="manage_categories"><AdminCategories /></AdminPanel> },
     }
 }
 
@@ -52,6 +61,54 @@ pub fn main_page() -> Html {
         });
     }
 
+    let unread_notifications: UseStateHandle<i64> = use_state_eq(|| 0);
+    let admin_page: UseStateHandle<Option<Route>> = use_state_eq(|| None);
+
+    // Show the admin panel's button if the logged in account can see any of its pages
+    {
+        let admin_page = admin_page.clone();
+
+        use_effect_with((*requested_account).as_ref().map(|account| account.id), move |account_id| {
+            if account_id.is_some() {
+                spawn_local(async move {
+                    let permissions = request_account_permissions().await.unwrap_or_default();
+
+                    admin_page.set(
+                        ADMIN_PAGES
+                            .into_iter()
+                            .find(|(required_permission, _, _)| permissions.iter().any(|permission| permission == required_permission))
+                            .map(|(_, _, route)| route),
+                    );
+                });
+            }
+        });
+    }
+
+    // Fetch the unread count once logged in, then refetch it every time the server pushes a new notification
+    {
+        let unread_notifications = unread_notifications.clone();
+
+        use_effect_with((*requested_account).as_ref().map(|account| account.id), move |account_id| {
+            let notification_socket = account_id.and_then(|_| {
+                let refresh_unread_count = Callback::from(move |_| {
+                    let unread_notifications = unread_notifications.clone();
+
+                    spawn_local(async move {
+                        if let Ok(unread_count) = request_unread_notification_count().await {
+                            unread_notifications.set(unread_count);
+                        }
+                    });
+                });
+
+                refresh_unread_count.emit(());
+
+                subscribe_notifications(Callback::from(move |_| refresh_unread_count.emit(()))).ok()
+            });
+
+            move || drop(notification_socket)
+        });
+    }
+
     html! {
         <>
             <div id="navigation">
@@ -68,6 +125,28 @@ pub fn main_page() -> Html {
                                         })
                                     }
                                 />
+                                {
+                                    if let Some(admin_page) = (*admin_page).clone() {
+                                        let navigator = navigator.clone();
+
+                                        html!(
+                                            <Button label={ "Admin" }
+                                                callback={Callback::from(move |_| navigator.push(&admin_page))}
+                                            />
+                                        )
+                                    }
+                                    else {
+                                        html!()
+                                    }
+                                }
+                                {
+                                    if *unread_notifications > 0 {
+                                        html!(<span id="notification_badge">{ *unread_notifications }</span>)
+                                    }
+                                    else {
+                                        html!()
+                                    }
+                                }
                             </>
                         )
                     }
@@ -98,8 +177,36 @@ pub fn main_page() -> Html {
             <div id="main_search">
             <h1>{ "Hasznalt.hu" }</h1>
                 <div id="search_bar">
-                    <TextField default_text={searchbar_text} text_buffer={search_buffer.clone()}/>
+                    <AutocompleteTextField default_text={searchbar_text} text_buffer={search_buffer.clone()}/>
                     <Button id="search_button" label={html!(<img src="public\\search.svg" height=20/>)} callback={Callback::from(|_| {})}/>
+                    {
+                        if requested_account.is_some() {
+                            html!(
+                                <Button label={ "Keresés mentése" }
+                                    callback={
+                                        let search_buffer = search_buffer.clone();
+                                        Callback::from(move |_| {
+                                            let query = search_buffer.to_string();
+
+                                            if query.trim().is_empty() {
+                                                return;
+                                            }
+
+                                            spawn_local(async move {
+                                                let _ = request_save_search(SavedSearchRequest {
+                                                    query,
+                                                    ..Default::default()
+                                                }).await;
+                                            });
+                                        })
+                                    }
+                                />
+                            )
+                        }
+                        else {
+                            html!()
+                        }
+                    }
                 </div>
             </div>
         </>
@@ -128,7 +235,7 @@ pub fn login_page() -> Html {
                 <TextField input_type="password" default_text={password_title} text_buffer={password_buffer.clone()}/>
 
                 <Button label={"Bejelentkezés"} callback={Callback::from(move |_| {
-                    let client = Client::new();
+                    let client = ApiClient::new();
                     let login_success = login_success.clone();
                     let post_request = client.post("http://[::1]:3004/api/login".to_string());
                     let password_buffer = password_buffer.clone();
@@ -206,7 +313,7 @@ pub fn register_page() -> Html {
                 <TextField input_type="password" default_text={password_title} text_buffer={password_buffer.clone()}/>
                 <Button label={"Regisztráció"} callback={
                     Callback::from(move |_| {
-                        let client = Client::new();
+                        let client = ApiClient::new();
 
                         let post_request = client.post("http://[::1]:3004/api/register".to_string());
                         let password_buffer = password_buffer.clone();
@@ -235,13 +342,20 @@ pub fn register_page() -> Html {
 pub fn account_page(AccountPageProperties { id }: &AccountPageProperties) -> Html {
     let requested_account: UseStateHandle<AccountLookup> = use_state_eq(AccountLookup::default);
 
-    let requested_account_clone = requested_account.clone();
+    {
+        let requested_account = requested_account.clone();
 
-    let id_clone = *id;
+        // Only refetch when the page switches to another account, not on every render
+        use_effect_with(*id, move |id| {
+            let id = *id;
 
-    spawn_local(async move {
-        requested_account_clone.set(request_account_lookup_from_id(id_clone).await.unwrap());
-    });
+            spawn_local(async move {
+                if let Ok(account) = request_account_lookup_from_id(id).await {
+                    requested_account.set(account);
+                }
+            });
+        });
+    }
 
     html!(
         <div id="username_title">
@@ -255,4 +369,471 @@ pub fn account_page(AccountPageProperties { id }: &AccountPageProperties) -> Htm
             </center>
         </div>
     )
-}
\ No newline at end of file
+}
+/// The pages of the admin panel, with the permission required to see them and the label of their navigation button.
+const ADMIN_PAGES: [(&str, &str, Route); 3] = [
+    ("view_statistics", "Statisztikák", Route::Admin),
+    ("manage_accounts", "Felhasználók", Route::AdminAccounts),
+    ("manage_categories", "Kategóriák", Route::AdminCategories),
+];
+
+#[derive(Properties, PartialEq)]
+pub struct AdminPanelProperties {
+    /// The permission the logged in account needs to see the page
+    pub permission: AttrValue,
+    /// The admin page
+    #[prop_or_default]
+    pub children: Html,
+}
+
+/// The frame of the admin pages, it only shows the page if the logged in account has the permission required by it.
+/// Please note that this is only for convenience, the `/api/admin/*` endpoints check the permissions themselves.
+#[function_component(AdminPanel)]
+pub fn admin_panel(AdminPanelProperties { permission, children }: &AdminPanelProperties) -> Html {
+    let navigator = use_navigator().unwrap();
+    let permissions: UseStateHandle<Option<Vec<String>>> = use_state_eq(|| None);
+
+    {
+        let permissions = permissions.clone();
+
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                permissions.set(Some(request_account_permissions().await.unwrap_or_default()));
+            });
+        });
+    }
+
+    let Some(permissions) = (*permissions).clone() else {
+        return html!();
+    };
+
+    if !permissions.contains(&permission.to_string()) {
+        return html!(
+            <div id="fail_prompt">
+                <h5>{ "Nincs jogosultsága az oldal megtekintéséhez!" }</h5>
+            </div>
+        );
+    }
+
+    let navigation_button = |label: &'static str, route: Route| {
+        let navigator = navigator.clone();
+
+        html!(
+            <Button label={ label } callback={Callback::from(move |_| navigator.push(&route))}/>
+        )
+    };
+
+    html!(
+        <div id="admin_panel">
+            <div id="admin_navigation">
+                { navigation_button("Főoldal", Route::MainPage) }
+                { for ADMIN_PAGES.into_iter()
+                    .filter(|(required_permission, _, _)| permissions.iter().any(|permission| permission == required_permission))
+                    .map(|(_, label, route)| navigation_button(label, route)) }
+            </div>
+            { children.clone() }
+        </div>
+    )
+}
+
+#[function_component(AdminStatistics)]
+pub fn admin_statistics_page() -> Html {
+    let statistics: UseStateHandle<Option<SiteStatistics>> = use_state_eq(|| None);
+
+    {
+        let statistics = statistics.clone();
+
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                statistics.set(request_admin_statistics().await.ok());
+            });
+        });
+    }
+
+    let Some(statistics) = (*statistics).clone() else {
+        return html!();
+    };
+
+    let rows = [
+        ("Felhasználók", statistics.account_count),
+        ("Rejtett felhasználók", statistics.hidden_account_count),
+        ("Felfüggesztett felhasználók", statistics.suspended_account_count),
+        ("Bejelentkezett munkamenetek", statistics.session_count),
+        ("Elbírálásra váró bejelentések", statistics.pending_report_count),
+        ("Kategóriák", statistics.category_count),
+        ("Mentett keresések", statistics.saved_search_count),
+    ];
+
+    html!(
+        <table class="admin_table">
+            { for rows.into_iter().map(|(label, value)| html!(
+                <tr>
+                    <td>{ label }</td>
+                    <td>{ value }</td>
+                </tr>
+            )) }
+        </table>
+    )
+}
+
+#[function_component(AdminAccounts)]
+pub fn admin_accounts_page() -> Html {
+    let search_title = use_state(|| String::from("Felhasználónév"));
+    let search_buffer = use_state(String::new);
+    let submitted_query = use_state_eq(String::new);
+    let page = use_state_eq(|| 0_i64);
+    let accounts: UseStateHandle<Vec<AdminAccount>> = use_state_eq(Vec::new);
+
+    // This gets incremented after every action, so that the list is reloaded with the new state of the accounts
+    let reload_generation = use_state_eq(|| 0_u32);
+
+    {
+        let accounts = accounts.clone();
+
+        use_effect_with(
+            ((*submitted_query).clone(), *page, *reload_generation),
+            move |(query, page, _)| {
+                let query = query.clone();
+                let page = *page;
+
+                spawn_local(async move {
+                    if let Ok(found_accounts) = request_admin_account_search(&query, page).await {
+                        accounts.set(found_accounts);
+                    }
+                });
+            },
+        );
+    }
+
+    let take_action = {
+        let reload_generation = reload_generation.clone();
+
+        Callback::from(move |(action, account_id): (AdminAccountAction, i32)| {
+            let reload_generation = reload_generation.clone();
+
+            spawn_local(async move {
+                let _ = request_admin_account_action(action, account_id).await;
+
+                reload_generation.set(reload_generation.wrapping_add(1));
+            });
+        })
+    };
+
+    let action_button = |label: &'static str, action: AdminAccountAction, account_id: i32| {
+        let take_action = take_action.clone();
+
+        html!(
+            <Button label={ label } callback={Callback::from(move |_| take_action.emit((action, account_id)))}/>
+        )
+    };
+
+    html!(
+        <>
+            <div id="admin_search">
+                <TextField default_text={search_title} text_buffer={search_buffer.clone()}/>
+                <Button label={ "Keresés" } callback={
+                    let submitted_query = submitted_query.clone();
+                    let page = page.clone();
+                    Callback::from(move |_| {
+                        submitted_query.set(search_buffer.to_string());
+                        page.set(0);
+                    })
+                }/>
+            </div>
+
+            <table class="admin_table">
+                { for accounts.iter().map(|account| {
+                    let status = if account.banned_at.is_some() {
+                        "Felfüggesztve"
+                    }
+                    else if account.hidden_at.is_some() {
+                        "Rejtett"
+                    }
+                    else {
+                        "Aktív"
+                    };
+
+                    html!(
+                        <tr>
+                            <td>{ account.id }</td>
+                            <td>{ account.username.clone() }</td>
+                            <td>{ account.created_at.to_string() }</td>
+                            <td>{ status }</td>
+                            <td>
+                                {
+                                    if account.banned_at.is_some() {
+                                        action_button("Feloldás", AdminAccountAction::Unsuspend, account.id)
+                                    }
+                                    else {
+                                        action_button("Felfüggesztés", AdminAccountAction::Suspend, account.id)
+                                    }
+                                }
+                                { action_button("Kijelentkeztetés", AdminAccountAction::RevokeSessions, account.id) }
+                            </td>
+                        </tr>
+                    )
+                }) }
+            </table>
+
+            <div id="admin_pagination">
+                <Button label={ "Előző" } callback={
+                    let page = page.clone();
+                    Callback::from(move |_| page.set((*page - 1).max(0)))
+                }/>
+                <span>{ *page + 1 }</span>
+                <Button label={ "Következő" } callback={
+                    let page = page.clone();
+                    Callback::from(move |_| page.set(*page + 1))
+                }/>
+            </div>
+        </>
+    )
+}
+
+/// This function turns the comma separated list of allowed values entered on the admin panel into a JSON list.
+/// The values are parsed as numbers for numeric attributes, values which can not be parsed are left as text so that the backend can reject them.
+fn parse_allowed_values(value_type: &str, input: &str) -> Option<serde_json::Value> {
+    let values = input
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| match value_type {
+            "integer" => value
+                .parse::<i64>()
+                .map(serde_json::Value::from)
+                .unwrap_or_else(|_| serde_json::Value::from(value)),
+            "number" => value
+                .parse::<f64>()
+                .map(serde_json::Value::from)
+                .unwrap_or_else(|_| serde_json::Value::from(value)),
+            _ => serde_json::Value::from(value),
+        })
+        .collect::<Vec<serde_json::Value>>();
+
+    (!values.is_empty()).then_some(serde_json::Value::Array(values))
+}
+
+#[function_component(AdminCategories)]
+pub fn admin_categories_page() -> Html {
+    let categories: UseStateHandle<Vec<Category>> = use_state_eq(Vec::new);
+    let selected_category: UseStateHandle<Option<i32>> = use_state_eq(|| None);
+    let attributes: UseStateHandle<Vec<CategoryAttribute>> = use_state_eq(Vec::new);
+    let failed: UseStateHandle<bool> = use_state_eq(|| false);
+
+    // This gets incremented after every change, so that the lists are reloaded
+    let reload_generation = use_state_eq(|| 0_u32);
+
+    let category_name_title = use_state(|| String::from("Kategória neve"));
+    let category_name_buffer = use_state(String::new);
+
+    let attribute_name_title = use_state(|| String::from("Kulcs (pl. mileage)"));
+    let attribute_name_buffer = use_state(String::new);
+    let attribute_label_title = use_state(|| String::from("Megnevezés"));
+    let attribute_label_buffer = use_state(String::new);
+    let attribute_unit_title = use_state(|| String::from("Mértékegység"));
+    let attribute_unit_buffer = use_state(String::new);
+    let attribute_allowed_values_title = use_state(|| String::from("Lehetséges értékek, vesszővel elválasztva"));
+    let attribute_allowed_values_buffer = use_state(String::new);
+    let attribute_value_type = use_state(|| String::from("text"));
+    let attribute_required = use_state(|| false);
+
+    {
+        let categories = categories.clone();
+
+        use_effect_with(*reload_generation, move |_| {
+            spawn_local(async move {
+                if let Ok(loaded_categories) = request_categories().await {
+                    categories.set(loaded_categories);
+                }
+            });
+        });
+    }
+
+    {
+        let attributes = attributes.clone();
+
+        use_effect_with((*selected_category, *reload_generation), move |(category_id, _)| {
+            let category_id = *category_id;
+
+            spawn_local(async move {
+                let loaded_attributes = match category_id {
+                    Some(category_id) => request_category_attributes(category_id).await.unwrap_or_default(),
+                    None => Vec::new(),
+                };
+
+                attributes.set(loaded_attributes);
+            });
+        });
+    }
+
+    // Runs the change and reloads the lists, the error prompt is shown if the change failed
+    let apply_change = {
+        let reload_generation = reload_generation.clone();
+        let failed = failed.clone();
+
+        move |change: std::pin::Pin<Box<dyn std::future::Future<Output = anyhow::Result<()>>>>| {
+            let reload_generation = reload_generation.clone();
+            let failed = failed.clone();
+
+            spawn_local(async move {
+                failed.set(change.await.is_err());
+
+                reload_generation.set(reload_generation.wrapping_add(1));
+            });
+        }
+    };
+
+    let selected_category_name = categories
+        .iter()
+        .find(|category| Some(category.id) == *selected_category)
+        .map(|category| category.name.clone());
+
+    html!(
+        <>
+            {
+                if *failed {
+                    html!(
+                        <div id="fail_prompt">
+                            <h5>{ "A módosítás nem sikerült!" }</h5>
+                        </div>
+                    )
+                }
+                else {
+                    html!()
+                }
+            }
+
+            <div id="admin_category_form">
+                <TextField default_text={category_name_title} text_buffer={category_name_buffer.clone()}/>
+                <Button label={ "Kategória létrehozása" } callback={
+                    let apply_change = apply_change.clone();
+                    Callback::from(move |_| {
+                        let name = category_name_buffer.to_string();
+
+                        apply_change(Box::pin(async move {
+                            request_admin_create_category(name).await.map(|_| ())
+                        }));
+                    })
+                }/>
+            </div>
+
+            <table class="admin_table">
+                { for categories.iter().map(|category| {
+                    let category_id = category.id;
+
+                    html!(
+                        <tr>
+                            <td>{ category.name.clone() }</td>
+                            <td>
+                                <Button label={ "Tulajdonságok" } callback={
+                                    let selected_category = selected_category.clone();
+                                    Callback::from(move |_| selected_category.set(Some(category_id)))
+                                }/>
+                                <Button label={ "Törlés" } callback={
+                                    let apply_change = apply_change.clone();
+                                    let selected_category = selected_category.clone();
+                                    Callback::from(move |_| {
+                                        if *selected_category == Some(category_id) {
+                                            selected_category.set(None);
+                                        }
+
+                                        apply_change(Box::pin(request_admin_delete_category(category_id)));
+                                    })
+                                }/>
+                            </td>
+                        </tr>
+                    )
+                }) }
+            </table>
+
+            {
+                if let (Some(category_id), Some(category_name)) = (*selected_category, selected_category_name) {
+                    html!(
+                        <div id="admin_attributes">
+                            <h3>{ format!("{category_name} tulajdonságai") }</h3>
+
+                            <table class="admin_table">
+                                { for attributes.iter().map(|attribute| {
+                                    let attribute_id = attribute.id;
+
+                                    html!(
+                                        <tr>
+                                            <td>{ attribute.name.clone() }</td>
+                                            <td>{ attribute.label.clone() }</td>
+                                            <td>{ attribute.value_type.clone() }</td>
+                                            <td>{ attribute.unit.clone().unwrap_or_default() }</td>
+                                            <td>{ attribute.allowed_values.as_ref().map(|allowed_values| allowed_values.to_string()).unwrap_or_default() }</td>
+                                            <td>{ if attribute.required { "Kötelező" } else { "" } }</td>
+                                            <td>
+                                                <Button label={ "Törlés" } callback={
+                                                    let apply_change = apply_change.clone();
+                                                    Callback::from(move |_| {
+                                                        apply_change(Box::pin(request_admin_delete_category_attribute(attribute_id)));
+                                                    })
+                                                }/>
+                                            </td>
+                                        </tr>
+                                    )
+                                }) }
+                            </table>
+
+                            <div id="admin_attribute_form">
+                                <TextField default_text={attribute_name_title} text_buffer={attribute_name_buffer.clone()}/>
+                                <TextField default_text={attribute_label_title} text_buffer={attribute_label_buffer.clone()}/>
+                                <select onchange={
+                                    let attribute_value_type = attribute_value_type.clone();
+                                    Callback::from(move |event: Event| {
+                                        let select: HtmlSelectElement = event.target_unchecked_into();
+
+                                        attribute_value_type.set(select.value());
+                                    })
+                                }>
+                                    <option value="text" selected={*attribute_value_type == "text"}>{ "Szöveg" }</option>
+                                    <option value="integer" selected={*attribute_value_type == "integer"}>{ "Egész szám" }</option>
+                                    <option value="number" selected={*attribute_value_type == "number"}>{ "Szám" }</option>
+                                    <option value="boolean" selected={*attribute_value_type == "boolean"}>{ "Igen/Nem" }</option>
+                                </select>
+                                <TextField default_text={attribute_unit_title} text_buffer={attribute_unit_buffer.clone()}/>
+                                <TextField default_text={attribute_allowed_values_title} text_buffer={attribute_allowed_values_buffer.clone()}/>
+                                <label>
+                                    <input type="checkbox" checked={*attribute_required} onchange={
+                                        let attribute_required = attribute_required.clone();
+                                        Callback::from(move |event: Event| {
+                                            let input: HtmlInputElement = event.target_unchecked_into();
+
+                                            attribute_required.set(input.checked());
+                                        })
+                                    }/>
+                                    { "Kötelező" }
+                                </label>
+                                <Button label={ "Tulajdonság hozzáadása" } callback={
+                                    let apply_change = apply_change.clone();
+                                    Callback::from(move |_| {
+                                        let unit = attribute_unit_buffer.trim().to_string();
+
+                                        let attribute = NewCategoryAttribute {
+                                            category_id,
+                                            name: attribute_name_buffer.trim().to_string(),
+                                            label: attribute_label_buffer.trim().to_string(),
+                                            value_type: attribute_value_type.to_string(),
+                                            unit: (!unit.is_empty()).then_some(unit),
+                                            allowed_values: parse_allowed_values(&attribute_value_type, &attribute_allowed_values_buffer),
+                                            required: *attribute_required,
+                                        };
+
+                                        apply_change(Box::pin(async move {
+                                            request_admin_create_category_attribute(attribute).await.map(|_| ())
+                                        }));
+                                    })
+                                }/>
+                            </div>
+                        </div>
+                    )
+                }
+                else {
+                    html!()
+                }
+            }
+        </>
+    )
+}
@@ -1,13 +1,15 @@
-use std::fmt::Display;
+use std::{fmt::Display, time::Duration};
 
-use reqwest::Client;
+use reqwest::{Client, IntoUrl, RequestBuilder};
 use serde::{Deserialize, Serialize};
-use wasm_bindgen::JsCast;
-use web_sys::{window, HtmlDocument, HtmlTextAreaElement};
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{
+    window, HtmlDocument, HtmlInputElement, HtmlTextAreaElement, MessageEvent, WebSocket,
+};
 use yew::html;
 use yew::{
-    virtual_dom::VNode, Callback, Component, InputEvent, MouseEvent, Properties, TargetCast,
-    UseStateHandle,
+    classes, platform::time::sleep, virtual_dom::VNode, Callback, Component, FocusEvent,
+    InputEvent, KeyboardEvent, MouseEvent, Properties, TargetCast, UseStateHandle,
 };
 
 /// ```TextField``` component definition
@@ -82,6 +84,197 @@ impl Component for TextField {
     }
 }
 
+/// ```AutocompleteTextField``` component definition
+/// This is a variant of ```TextField``` which suggests completions for the entered text.
+/// The suggestions can be navigated with the arrow keys, picked with enter and dismissed with escape.
+pub struct AutocompleteTextField {
+    /// The suggestions for the currently entered text
+    suggestions: Vec<String>,
+    /// The index of the suggestion highlighted via the keyboard
+    highlighted: Option<usize>,
+    /// This gets incremented on every input, so that outdated timers and responses can be ignored
+    input_generation: u32,
+}
+
+impl AutocompleteTextField {
+    /// The time the user has to stop typing for before suggestions are requested
+    pub const DEBOUNCE: Duration = Duration::from_millis(250);
+}
+
+/// The ```AutocompleteTextField``` update's messages
+pub enum AutocompleteTextFieldMessage {
+    /// This get sent when the AutocompleteTextField has it's buffer updated
+    ValueUpdate(String),
+    /// This gets sent when the user has stopped typing for ```AutocompleteTextField::DEBOUNCE```
+    DebounceElapsed(u32),
+    /// This gets sent when the server has responded with the suggestions
+    SuggestionsLoaded(u32, Vec<String>),
+    /// This gets sent when a key is pressed while the AutocompleteTextField is focused
+    KeyDown(KeyboardEvent),
+    /// This gets sent when a suggestion is picked
+    Select(usize),
+    /// This gets sent when the suggestions should be hidden
+    Close,
+}
+
+impl Component for AutocompleteTextField {
+    type Message = AutocompleteTextFieldMessage;
+
+    type Properties = TextFieldProperties;
+
+    fn create(_ctx: &yew::Context<Self>) -> Self {
+        Self {
+            suggestions: Vec::new(),
+            highlighted: None,
+            input_generation: 0,
+        }
+    }
+
+    fn view(&self, ctx: &yew::Context<Self>) -> yew::Html {
+        let text_edit_value_callback: Callback<InputEvent> =
+            ctx.link().callback(move |event: InputEvent| {
+                let html_elem: HtmlInputElement = event.target_unchecked_into();
+
+                AutocompleteTextFieldMessage::ValueUpdate(html_elem.value())
+            });
+
+        let key_down_callback = ctx.link().callback(AutocompleteTextFieldMessage::KeyDown);
+
+        let blur_callback = ctx
+            .link()
+            .callback(|_: FocusEvent| AutocompleteTextFieldMessage::Close);
+
+        html!(
+            <div class="autocomplete">
+                <input id={ctx.props().id.clone()} type={ctx.props().input_type.clone()} autocomplete="off" placeholder={ctx.props().default_text.to_string()} value={ctx.props().text_buffer.to_string()} oninput={text_edit_value_callback} onkeydown={key_down_callback} onblur={blur_callback}/>
+                {
+                    if self.suggestions.is_empty() {
+                        html!()
+                    }
+                    else {
+                        html!(
+                            <ul class="autocomplete_suggestions">
+                                {
+                                    for self.suggestions.iter().enumerate().map(|(index, suggestion)| {
+                                        // Mousedown is used instead of click, as click would only fire after the input has lost focus and the suggestions are closed
+                                        let select_callback = ctx.link().callback(move |_: MouseEvent| AutocompleteTextFieldMessage::Select(index));
+
+                                        html!(
+                                            <li class={classes!((self.highlighted == Some(index)).then_some("highlighted"))} onmousedown={select_callback}>{ suggestion }</li>
+                                        )
+                                    })
+                                }
+                            </ul>
+                        )
+                    }
+                }
+            </div>
+        )
+    }
+
+    fn update(&mut self, ctx: &yew::Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            AutocompleteTextFieldMessage::ValueUpdate(msg) => {
+                ctx.props().text_buffer.set(msg);
+
+                self.input_generation = self.input_generation.wrapping_add(1);
+
+                let input_generation = self.input_generation;
+
+                ctx.link().send_future(async move {
+                    sleep(Self::DEBOUNCE).await;
+
+                    AutocompleteTextFieldMessage::DebounceElapsed(input_generation)
+                });
+
+                true
+            }
+            AutocompleteTextFieldMessage::DebounceElapsed(input_generation) => {
+                // The user has typed since this timer was started
+                if input_generation != self.input_generation {
+                    return false;
+                }
+
+                let query = ctx.props().text_buffer.to_string();
+
+                ctx.link().send_future(async move {
+                    AutocompleteTextFieldMessage::SuggestionsLoaded(
+                        input_generation,
+                        request_search_suggestions(&query).await.unwrap_or_default(),
+                    )
+                });
+
+                false
+            }
+            AutocompleteTextFieldMessage::SuggestionsLoaded(input_generation, suggestions) => {
+                if input_generation != self.input_generation {
+                    return false;
+                }
+
+                self.suggestions = suggestions;
+                self.highlighted = None;
+
+                true
+            }
+            AutocompleteTextFieldMessage::KeyDown(event) => {
+                if self.suggestions.is_empty() {
+                    return false;
+                }
+
+                let last_index = self.suggestions.len() - 1;
+
+                match event.key().as_str() {
+                    "ArrowDown" => {
+                        event.prevent_default();
+
+                        self.highlighted = Some(match self.highlighted {
+                            Some(index) if index < last_index => index + 1,
+                            _ => 0,
+                        });
+
+                        true
+                    }
+                    "ArrowUp" => {
+                        event.prevent_default();
+
+                        self.highlighted = Some(match self.highlighted {
+                            Some(index) if index > 0 => index - 1,
+                            _ => last_index,
+                        });
+
+                        true
+                    }
+                    "Enter" => match self.highlighted {
+                        Some(index) => {
+                            event.prevent_default();
+
+                            self.update(ctx, AutocompleteTextFieldMessage::Select(index))
+                        }
+                        None => false,
+                    },
+                    "Escape" => self.update(ctx, AutocompleteTextFieldMessage::Close),
+                    _ => false,
+                }
+            }
+            AutocompleteTextFieldMessage::Select(index) => {
+                if let Some(suggestion) = self.suggestions.get(index) {
+                    ctx.props().text_buffer.set(suggestion.clone());
+                }
+
+                self.update(ctx, AutocompleteTextFieldMessage::Close)
+            }
+            AutocompleteTextFieldMessage::Close => {
+                // Invalidate the pending timers and responses, so that the suggestions dont reappear
+                self.input_generation = self.input_generation.wrapping_add(1);
+                self.suggestions.clear();
+                self.highlighted = None;
+
+                true
+            }
+        }
+    }
+}
+
 /// ```Button``` component definition
 pub struct Button;
 
@@ -161,33 +354,433 @@ pub fn get_cookie(name: &str) -> Option<String> {
     wasm_cookies::cookies::get(&cookies, name)?.ok()
 }
 
+/// The cookie the backend stores the CSRF token in
+const CSRF_COOKIE_NAME: &str = "csrf_token";
+
+/// The header the backend expects the CSRF token back in on every state-changing request
+const CSRF_HEADER: &str = "X-CSRF-Token";
+
+/// This struct should be used for every request made to the backend, it attaches the CSRF token to every state-changing request, which the backend rejects otherwise.
+#[derive(Clone, Debug, Default)]
+pub struct ApiClient {
+    client: Client,
+}
+
+impl ApiClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.client.get(url)
+    }
+
+    pub fn post<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        with_csrf_token(self.client.post(url))
+    }
+
+    pub fn put<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        with_csrf_token(self.client.put(url))
+    }
+
+    pub fn delete<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        with_csrf_token(self.client.delete(url))
+    }
+}
+
+/// This function copies the CSRF token from its cookie into the header of the request.
+/// The backend issues the cookie with the first response, so it is only missing if the page wasnt served by the backend.
+fn with_csrf_token(request: RequestBuilder) -> RequestBuilder {
+    match get_cookie(CSRF_COOKIE_NAME) {
+        Some(token) => request.header(CSRF_HEADER, token),
+        None => request,
+    }
+}
+
 
 pub async fn request_account_lookup_from_id(id: i32) -> anyhow::Result<AccountLookup> {
-    let client = Client::new();
+    let client = ApiClient::new();
+
+    let get_request = client.get(format!("http://[::1]:3004/api/v1/accounts/{id}"));
+
+    let response = get_request.send().await?;
+
+    let server_response = response.text().await?;
+
+    Ok(serde_json::from_str::<AccountLookup>(&server_response)?)
+}
+
+pub async fn request_search_suggestions(query: &str) -> anyhow::Result<Vec<String>> {
+    let client = ApiClient::new();
+
+    let get_request = client
+        .get("http://[::1]:3004/api/search/suggest")
+        .query(&[("q", query)]);
+
+    let response = get_request.send().await?;
+
+    let server_response = response.text().await?;
+
+    Ok(serde_json::from_str::<Vec<String>>(&server_response)?)
+}
 
-    let post_request = client.post("http://[::1]:3004/api/id_lookup");
+pub async fn request_account_lookup_from_cookie() -> anyhow::Result<AccountLookup> {
+    let client = ApiClient::new();
+
+    let get_request = client.get("http://[::1]:3004/api/v1/me");
+
+    let response = get_request.send().await?;
+
+    let server_response = response.text().await?;
+
+    Ok(serde_json::from_str::<AccountLookup>(&server_response)?)
+}
+
+/// How often the owner of a saved search wants to be alerted about new matches.
+/// The backend doesnt send alerts yet, so this is not shown to the user, every search is saved with the default.
+#[derive(Default, Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertFrequency {
+    Instant,
+    #[default]
+    Daily,
+    Weekly,
+    Never,
+}
+
+#[derive(Default, Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct SavedSearchRequest {
+    /// The text the user has entered into the search bar
+    pub query: String,
+    /// The category the search is narrowed to
+    pub category: Option<String>,
+    /// The lowest price a listing can have to match
+    pub min_price: Option<i32>,
+    /// The highest price a listing can have to match
+    pub max_price: Option<i32>,
+    /// The location the search is narrowed to
+    pub location: Option<String>,
+    /// How often the owner wants to be alerted about new matches
+    pub alert_frequency: AlertFrequency,
+}
+
+pub async fn request_save_search(request: SavedSearchRequest) -> anyhow::Result<()> {
+    let client = ApiClient::new();
+
+    let post_request = client.post("http://[::1]:3004/api/saved_search/create");
 
     let response = post_request
         .header("Content-Type", "application/json")
-        .body(id.to_string())
+        .body(serde_json::to_string(&request)?)
         .send()
         .await?;
 
+    response.error_for_status()?;
+
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Notification {
+    /// The id of the notification
+    pub id: i32,
+    /// The UUID of the account which received this notification
+    pub account_id: i32,
+    /// The kind of the notification
+    pub kind: String,
+    /// The title of the notification
+    pub title: String,
+    /// The body of the notification
+    pub body: String,
+    /// The frontend path the notification should lead to when clicked
+    pub link: Option<String>,
+    /// The timestamp taken when the notification was read, this is ```None``` if it is unread
+    pub read_at: Option<chrono::NaiveDateTime>,
+    /// The timestamp taken when the notification was created
+    pub created_at: chrono::NaiveDateTime,
+}
+
+pub async fn request_unread_notification_count() -> anyhow::Result<i64> {
+    let client = ApiClient::new();
+
+    let get_request = client.get("http://[::1]:3004/api/notifications/unread_count");
+
+    let response = get_request.send().await?;
+
     let server_response = response.text().await?;
 
-    Ok(serde_json::from_str::<AccountLookup>(&server_response)?)
+    Ok(serde_json::from_str::<i64>(&server_response)?)
 }
 
-pub async fn request_account_lookup_from_cookie() -> anyhow::Result<AccountLookup> {
-    let client = Client::new();
+/// A live connection which the logged in account's new notifications are pushed through.
+/// The connection gets closed when this is dropped.
+pub struct NotificationSocket {
+    socket: WebSocket,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+}
+
+impl Drop for NotificationSocket {
+    fn drop(&mut self) {
+        self.socket.set_onmessage(None);
+
+        let _ = self.socket.close();
+    }
+}
+
+/// Opens a ```NotificationSocket```, the callback gets called with every ```Notification``` pushed by the server.
+pub fn subscribe_notifications(
+    callback: Callback<Notification>,
+) -> anyhow::Result<NotificationSocket> {
+    let socket = WebSocket::new("ws://[::1]:3004/api/notifications/ws")
+        .map_err(|err| anyhow::Error::msg(format!("{err:?}")))?;
+
+    let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+        if let Some(text) = event.data().as_string() {
+            if let Ok(notification) = serde_json::from_str::<Notification>(&text) {
+                callback.emit(notification);
+            }
+        }
+    });
+
+    socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
+    Ok(NotificationSocket {
+        socket,
+        _on_message: on_message,
+    })
+}
+
+/// An account as it is shown to admins, including its moderation state.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct AdminAccount {
+    /// The UUID of the account
+    pub id: i32,
+    /// The username of the account
+    pub username: String,
+    /// The timestamp taken when the account was created
+    pub created_at: chrono::NaiveDate,
+    /// The timestamp taken when the account got hidden, this is ```None``` if the account is visible
+    pub hidden_at: Option<chrono::NaiveDateTime>,
+    /// The timestamp the account stays hidden until, this is ```None``` if it is hidden until it gets restored
+    pub hidden_until: Option<chrono::NaiveDateTime>,
+    /// The timestamp taken when the account got suspended, this is ```None``` if the account can log in
+    pub banned_at: Option<chrono::NaiveDateTime>,
+}
+
+/// The statistics shown on the admin panel.
+#[derive(Default, Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct SiteStatistics {
+    pub account_count: i64,
+    pub hidden_account_count: i64,
+    pub suspended_account_count: i64,
+    pub session_count: i64,
+    pub pending_report_count: i64,
+    pub category_count: i64,
+    pub saved_search_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Category {
+    /// The id of the category
+    pub id: i32,
+    /// The displayed name of the category
+    pub name: String,
+    /// The id of the category this one is nested in
+    pub parent_id: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct CategoryAttribute {
+    /// The id of the attribute
+    pub id: i32,
+    /// The id of the category this attribute belongs to
+    pub category_id: i32,
+    /// The key the attribute is stored under
+    pub name: String,
+    /// The displayed name of the attribute
+    pub label: String,
+    /// The kind of value this attribute accepts (`integer`, `number`, `text` or `boolean`)
+    pub value_type: String,
+    /// The unit the value is measured in
+    pub unit: Option<String>,
+    /// The list of values this attribute can take
+    pub allowed_values: Option<serde_json::Value>,
+    /// Whether the attribute has to be present on every listing in the category
+    pub required: bool,
+}
+
+#[derive(Default, Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct NewCategoryAttribute {
+    pub category_id: i32,
+    pub name: String,
+    pub label: String,
+    pub value_type: String,
+    pub unit: Option<String>,
+    pub allowed_values: Option<serde_json::Value>,
+    pub required: bool,
+}
+
+/// The actions an admin can take against an account.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AdminAccountAction {
+    Suspend,
+    Unsuspend,
+    RevokeSessions,
+}
+
+impl AdminAccountAction {
+    /// The path of the endpoint taking this action.
+    fn path(&self) -> &'static str {
+        match self {
+            AdminAccountAction::Suspend => "accounts/suspend",
+            AdminAccountAction::Unsuspend => "accounts/unsuspend",
+            AdminAccountAction::RevokeSessions => "accounts/sessions/revoke",
+        }
+    }
+}
+
+pub async fn request_account_permissions() -> anyhow::Result<Vec<String>> {
+    let client = ApiClient::new();
+
+    let get_request = client.get("http://[::1]:3004/api/account/permissions");
+
+    let response = get_request.send().await?.error_for_status()?;
+
+    let server_response = response.text().await?;
+
+    Ok(serde_json::from_str::<Vec<String>>(&server_response)?)
+}
+
+pub async fn request_admin_statistics() -> anyhow::Result<SiteStatistics> {
+    let client = ApiClient::new();
+
+    let get_request = client.get("http://[::1]:3004/api/admin/stats");
+
+    let response = get_request.send().await?.error_for_status()?;
+
+    let server_response = response.text().await?;
+
+    Ok(serde_json::from_str::<SiteStatistics>(&server_response)?)
+}
+
+pub async fn request_admin_account_search(
+    query: &str,
+    page: i64,
+) -> anyhow::Result<Vec<AdminAccount>> {
+    let client = ApiClient::new();
+
+    let get_request = client
+        .get("http://[::1]:3004/api/admin/accounts")
+        .query(&[("q", query), ("page", &page.to_string())]);
+
+    let response = get_request.send().await?.error_for_status()?;
+
+    let server_response = response.text().await?;
+
+    Ok(serde_json::from_str::<Vec<AdminAccount>>(&server_response)?)
+}
+
+pub async fn request_admin_account_action(
+    action: AdminAccountAction,
+    account_id: i32,
+) -> anyhow::Result<()> {
+    let client = ApiClient::new();
 
-    let post_request = client.post("http://[::1]:3004/api/account");
+    let post_request = client.post(format!("http://[::1]:3004/api/admin/{}", action.path()));
 
     let response = post_request
+        .header("Content-Type", "application/json")
+        .body(serde_json::json!({ "account_id": account_id, "note": null }).to_string())
         .send()
         .await?;
 
+    response.error_for_status()?;
+
+    Ok(())
+}
+
+pub async fn request_categories() -> anyhow::Result<Vec<Category>> {
+    let client = ApiClient::new();
+
+    let get_request = client.get("http://[::1]:3004/api/categories");
+
+    let response = get_request.send().await?;
+
     let server_response = response.text().await?;
 
-    Ok(serde_json::from_str::<AccountLookup>(&server_response)?)
+    Ok(serde_json::from_str::<Vec<Category>>(&server_response)?)
+}
+
+pub async fn request_category_attributes(category_id: i32) -> anyhow::Result<Vec<CategoryAttribute>> {
+    let client = ApiClient::new();
+
+    let get_request =
+        client.get(format!("http://[::1]:3004/api/categories/{category_id}/attributes"));
+
+    let response = get_request.send().await?;
+
+    let server_response = response.text().await?;
+
+    Ok(serde_json::from_str::<Vec<CategoryAttribute>>(&server_response)?)
+}
+
+pub async fn request_admin_create_category(name: String) -> anyhow::Result<Category> {
+    let client = ApiClient::new();
+
+    let post_request = client.post("http://[::1]:3004/api/admin/categories/create");
+
+    let response = post_request
+        .header("Content-Type", "application/json")
+        .body(serde_json::json!({ "name": name, "parent_id": null }).to_string())
+        .send()
+        .await?
+        .error_for_status()?;
+
+    let server_response = response.text().await?;
+
+    Ok(serde_json::from_str::<Category>(&server_response)?)
+}
+
+pub async fn request_admin_create_category_attribute(
+    attribute: NewCategoryAttribute,
+) -> anyhow::Result<CategoryAttribute> {
+    let client = ApiClient::new();
+
+    let post_request = client.post("http://[::1]:3004/api/admin/categories/attributes/create");
+
+    let response = post_request
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(&attribute)?)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    let server_response = response.text().await?;
+
+    Ok(serde_json::from_str::<CategoryAttribute>(&server_response)?)
+}
+
+pub async fn request_admin_delete_category(category_id: i32) -> anyhow::Result<()> {
+    request_admin_delete("categories/delete", category_id).await
+}
+
+pub async fn request_admin_delete_category_attribute(attribute_id: i32) -> anyhow::Result<()> {
+    request_admin_delete("categories/attributes/delete", attribute_id).await
+}
+
+/// Posts the id of the entry to delete to the admin endpoint at ```path```.
+async fn request_admin_delete(path: &str, id: i32) -> anyhow::Result<()> {
+    let client = ApiClient::new();
+
+    let post_request = client.post(format!("http://[::1]:3004/api/admin/{path}"));
+
+    let response = post_request
+        .header("Content-Type", "application/json")
+        .body(id.to_string())
+        .send()
+        .await?;
+
+    response.error_for_status()?;
+
+    Ok(())
 }
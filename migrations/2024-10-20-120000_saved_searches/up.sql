@@ -0,0 +1,14 @@
+CREATE TABLE saved_searches (
+  id INT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+  account_id INT NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+  query VARCHAR NOT NULL,
+  category VARCHAR,
+  min_price INT,
+  max_price INT,
+  location VARCHAR,
+  alert_frequency VARCHAR NOT NULL DEFAULT 'daily',
+  last_alerted_at TIMESTAMP,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX saved_searches_account_id_idx ON saved_searches (account_id)
\ No newline at end of file
@@ -0,0 +1,13 @@
+CREATE TABLE notifications (
+  id INT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+  account_id INT NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+  kind VARCHAR NOT NULL,
+  title VARCHAR NOT NULL,
+  body VARCHAR NOT NULL,
+  link VARCHAR,
+  read_at TIMESTAMP,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX notifications_account_id_idx ON notifications (account_id, id DESC);
+CREATE INDEX notifications_unread_idx ON notifications (account_id) WHERE read_at IS NULL
\ No newline at end of file
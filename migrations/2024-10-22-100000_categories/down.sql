@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE category_attributes;
+DROP TABLE categories;
\ No newline at end of file
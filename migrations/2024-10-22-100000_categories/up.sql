@@ -0,0 +1,30 @@
+CREATE TABLE categories (
+  id INT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+  name VARCHAR NOT NULL UNIQUE,
+  parent_id INT REFERENCES categories (id) ON DELETE CASCADE
+);
+
+CREATE TABLE category_attributes (
+  id INT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+  category_id INT NOT NULL REFERENCES categories (id) ON DELETE CASCADE,
+  name VARCHAR NOT NULL,
+  label VARCHAR NOT NULL,
+  value_type VARCHAR NOT NULL,
+  unit VARCHAR,
+  allowed_values JSONB,
+  required BOOLEAN NOT NULL DEFAULT FALSE,
+  UNIQUE (category_id, name)
+);
+
+INSERT INTO categories (name) VALUES ('Autók'), ('Telefonok'), ('Ruházat');
+
+INSERT INTO category_attributes (category_id, name, label, value_type, unit, allowed_values, required)
+SELECT categories.id, attribute.name, attribute.label, attribute.value_type, attribute.unit, attribute.allowed_values, attribute.required
+FROM (VALUES
+  ('Autók', 'year', 'Évjárat', 'integer', NULL, NULL::JSONB, TRUE),
+  ('Autók', 'mileage', 'Kilométeróra állása', 'integer', 'km', NULL, TRUE),
+  ('Autók', 'fuel', 'Üzemanyag', 'text', NULL, '["benzin", "dízel", "hibrid", "elektromos"]'::JSONB, FALSE),
+  ('Telefonok', 'storage', 'Tárhely', 'integer', 'GB', NULL, TRUE),
+  ('Ruházat', 'size', 'Méret', 'text', NULL, '["XS", "S", "M", "L", "XL", "XXL"]'::JSONB, TRUE)
+) AS attribute (category, name, label, value_type, unit, allowed_values, required)
+JOIN categories ON categories.name = attribute.category
\ No newline at end of file
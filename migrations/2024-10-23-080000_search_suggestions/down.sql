@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX categories_name_trgm_idx;
\ No newline at end of file
@@ -0,0 +1,3 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX categories_name_trgm_idx ON categories USING GIN (name gin_trgm_ops)
\ No newline at end of file
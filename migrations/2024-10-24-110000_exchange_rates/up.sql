@@ -0,0 +1,6 @@
+-- Rates are stored as the number of millionths of a forint one minor unit of the currency is worth
+CREATE TABLE exchange_rates (
+  currency VARCHAR PRIMARY KEY NOT NULL,
+  rate_to_huf BIGINT NOT NULL CHECK (rate_to_huf > 0),
+  updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+)
\ No newline at end of file
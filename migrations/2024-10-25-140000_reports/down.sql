@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE moderation_log;
+DROP TABLE reports;
+
+ALTER TABLE accounts
+  DROP COLUMN hidden_at,
+  DROP COLUMN hidden_until,
+  DROP COLUMN banned_at;
\ No newline at end of file
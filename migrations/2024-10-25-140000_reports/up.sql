@@ -0,0 +1,31 @@
+ALTER TABLE accounts
+  ADD COLUMN hidden_at TIMESTAMP,
+  ADD COLUMN hidden_until TIMESTAMP,
+  ADD COLUMN banned_at TIMESTAMP;
+
+CREATE TABLE reports (
+  id INT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+  reporter_id INT NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+  target_kind VARCHAR NOT NULL,
+  target_id INT NOT NULL,
+  reason VARCHAR NOT NULL,
+  details VARCHAR,
+  status VARCHAR NOT NULL DEFAULT 'pending',
+  created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  resolved_by INT REFERENCES accounts (id) ON DELETE SET NULL,
+  resolved_at TIMESTAMP
+);
+
+-- An account can only have one pending report against the same target, so that the reports counted are from distinct reporters
+CREATE UNIQUE INDEX reports_pending_reporter_idx ON reports (reporter_id, target_kind, target_id) WHERE status = 'pending';
+CREATE INDEX reports_pending_target_idx ON reports (target_kind, target_id) WHERE status = 'pending';
+
+CREATE TABLE moderation_log (
+  id INT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+  moderator_id INT REFERENCES accounts (id) ON DELETE SET NULL,
+  action VARCHAR NOT NULL,
+  target_kind VARCHAR NOT NULL,
+  target_id INT NOT NULL,
+  note VARCHAR,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW()
+)
\ No newline at end of file
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE account_roles;
+DROP TABLE role_permissions;
+DROP TABLE roles;
\ No newline at end of file
@@ -0,0 +1,28 @@
+CREATE TABLE roles (
+  id INT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+  name VARCHAR NOT NULL UNIQUE
+);
+
+CREATE TABLE role_permissions (
+  role_id INT NOT NULL REFERENCES roles (id) ON DELETE CASCADE,
+  permission VARCHAR NOT NULL,
+  PRIMARY KEY (role_id, permission)
+);
+
+CREATE TABLE account_roles (
+  account_id INT NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+  role_id INT NOT NULL REFERENCES roles (id) ON DELETE CASCADE,
+  PRIMARY KEY (account_id, role_id)
+);
+
+INSERT INTO roles (name) VALUES ('admin'), ('moderator');
+
+INSERT INTO role_permissions (role_id, permission)
+SELECT roles.id, role_permission.permission
+FROM (VALUES
+  ('admin', 'moderate_reports'),
+  ('admin', 'manage_exchange_rates'),
+  ('admin', 'manage_roles'),
+  ('moderator', 'moderate_reports')
+) AS role_permission (role, permission)
+JOIN roles ON roles.name = role_permission.role
\ No newline at end of file
@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+DELETE FROM role_permissions WHERE permission IN ('manage_accounts', 'manage_categories', 'view_statistics')
\ No newline at end of file
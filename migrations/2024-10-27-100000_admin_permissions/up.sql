@@ -0,0 +1,4 @@
+INSERT INTO role_permissions (role_id, permission)
+SELECT roles.id, permission
+FROM roles, (VALUES ('manage_accounts'), ('manage_categories'), ('view_statistics')) AS admin_permission (permission)
+WHERE roles.name = 'admin'
\ No newline at end of file
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DELETE FROM role_permissions WHERE permission = 'view_audit_log';
+DROP TABLE audit_events;
+DROP FUNCTION reject_audit_event_update
\ No newline at end of file
@@ -0,0 +1,31 @@
+-- The account is not a foreign key, so that the events of deleted accounts are kept
+CREATE TABLE audit_events (
+  id INT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+  account_id INT,
+  username VARCHAR,
+  ip_address VARCHAR,
+  user_agent VARCHAR,
+  event_type VARCHAR NOT NULL,
+  outcome VARCHAR NOT NULL,
+  details VARCHAR,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX audit_events_account_idx ON audit_events (account_id, created_at);
+CREATE INDEX audit_events_created_at_idx ON audit_events (created_at);
+
+-- Events can only be added or pruned, never changed
+CREATE FUNCTION reject_audit_event_update() RETURNS TRIGGER AS $$
+BEGIN
+  RAISE EXCEPTION 'audit_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_events_append_only
+BEFORE UPDATE ON audit_events
+FOR EACH ROW EXECUTE FUNCTION reject_audit_event_update();
+
+INSERT INTO role_permissions (role_id, permission)
+SELECT roles.id, 'view_audit_log'
+FROM roles
+WHERE roles.name = 'admin'
\ No newline at end of file
@@ -0,0 +1,10 @@
+CREATE TABLE account_blocks (
+  blocker_id INT NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+  blocked_id INT NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (blocker_id, blocked_id),
+  CHECK (blocker_id <> blocked_id)
+);
+
+-- Used when checking whether an account is blocked by the other party of an interaction
+CREATE INDEX account_blocks_blocked_idx ON account_blocks (blocked_id)
\ No newline at end of file
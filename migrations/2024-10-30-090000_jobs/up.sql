@@ -0,0 +1,23 @@
+-- Jobs are deleted once they succeed, so only the pending, running and dead jobs are kept
+CREATE TABLE jobs (
+  id INT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+  kind VARCHAR NOT NULL,
+  payload JSONB NOT NULL,
+  status VARCHAR NOT NULL DEFAULT 'pending',
+  attempts INT NOT NULL DEFAULT 0,
+  max_attempts INT NOT NULL,
+  run_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  locked_at TIMESTAMP,
+  last_error VARCHAR,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  CHECK (max_attempts > 0)
+);
+
+-- Used by the workers when claiming the next due job
+CREATE INDEX jobs_claimable_idx ON jobs (run_at) WHERE status IN ('pending', 'running');
+
+-- The next run of every recurring job, the row is locked while the job is being enqueued so that only one server instance enqueues it
+CREATE TABLE job_schedules (
+  name VARCHAR PRIMARY KEY,
+  next_run_at TIMESTAMP NOT NULL
+)